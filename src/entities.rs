@@ -1,5 +1,4 @@
 pub mod config;
 pub mod counter;
 pub mod event;
-pub mod tetrimono;
-pub mod tetris;
+pub mod game;
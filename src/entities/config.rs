@@ -1,19 +1,363 @@
-use bevy::prelude::States;
-
-pub const TETRIMONO_MATRIX_SIZE: usize = 4;
-pub const MAX_HEIGHT: usize = 22;
-pub const MAX_WIDTH: usize = 10;
-pub const MIN_QUEUE_MONO_COUNT: usize = 7;
-pub const INITIAL_DROP_FRAMES: i64 = 48;
-pub const INITIAL_MONO_POSITION: (usize, usize) = (20, 5);
-pub const HEIGHT_WITH_PADDING: usize = MAX_HEIGHT + HEIGHT_PADDING;
-pub const WIDTH_WITH_PADDING: usize = MAX_WIDTH + WIDTH_PADDING;
-pub const WIDTH_PADDING: usize = 4;
-pub const HEIGHT_PADDING: usize = 4;
+use std::collections::HashMap;
+use std::path::Path;
+
+use bevy::prelude::{KeyCode, Resource, States};
+use tetris_core::ai::{AiDifficulty, AI_PRESETS, MAX_SEARCH_DEPTH};
+use tetris_core::config::{GameSettings, FRAMES_PER_SECOND, MAX_NEXT_COUNT};
+use tetris_core::counter::LockResetRule;
+use tetris_core::garbage::GarbageRules;
+use tetris_core::handling::Handling;
+use tetris_core::mode::GameMode;
+use tetris_core::randomizer::RandomizerType;
+use tetris_core::rotation::RotationSystemType;
+use tetris_core::versus::PLAYER_COUNT;
+
+pub const COUNTDOWN_FRAMES: i64 = 3 * FRAMES_PER_SECOND;
+pub const GAME_OVER_FRAMES: i64 = 2 * FRAMES_PER_SECOND;
+pub const WINDOW_WIDTH: f32 = 500.;
+pub const WINDOW_HEIGHT: f32 = 676.;
+// 対戦中は 2 人分のボードを横に並べる
+pub const VERSUS_WINDOW_WIDTH: f32 = 1000.;
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum AppState {
     #[default]
     Menu,
+    Countdown,
     InGame,
+    Paused,
+    GameOver,
+    Results,
+    Replay,
+    Versus,
+    VersusPaused,
+    VersusResults,
+}
+
+// メニューで選べる項目 上から順に並べる
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuItem {
+    Players,
+    Mode,
+    Rotation,
+    Randomizer,
+    Seed,
+}
+
+impl MenuItem {
+    pub const ALL: [MenuItem; 5] = [
+        MenuItem::Players,
+        MenuItem::Mode,
+        MenuItem::Rotation,
+        MenuItem::Randomizer,
+        MenuItem::Seed,
+    ];
+}
+
+// メニューで選んでいる行
+#[derive(Resource, Default)]
+pub struct MenuCursor {
+    pub row: usize,
+}
+
+impl MenuCursor {
+    pub fn item(&self) -> MenuItem {
+        return MenuItem::ALL[self.row % MenuItem::ALL.len()];
+    }
+
+    pub fn up(&mut self) {
+        self.row = (self.row + MenuItem::ALL.len() - 1) % MenuItem::ALL.len();
+    }
+
+    pub fn down(&mut self) {
+        self.row = (self.row + 1) % MenuItem::ALL.len();
+    }
+}
+
+// ボタンごとに割り当てたキー
+pub struct KeyMap {
+    pub left: &'static [KeyCode],
+    pub right: &'static [KeyCode],
+    pub soft_drop: &'static [KeyCode],
+    pub hard_drop: &'static [KeyCode],
+    pub rotate_right: &'static [KeyCode],
+    pub rotate_left: &'static [KeyCode],
+    pub rotate_half: &'static [KeyCode],
+    pub hold: &'static [KeyCode],
+}
+
+pub const SINGLE_KEY_MAP: KeyMap = KeyMap {
+    left: &[KeyCode::Left],
+    right: &[KeyCode::Right],
+    soft_drop: &[KeyCode::Down],
+    hard_drop: &[KeyCode::Space],
+    rotate_right: &[KeyCode::Up, KeyCode::X],
+    rotate_left: &[KeyCode::Z],
+    rotate_half: &[KeyCode::A],
+    hold: &[KeyCode::C, KeyCode::LShift],
+};
+
+// 対戦では左のプレイヤーが WASD 側、右のプレイヤーが矢印キー側を使う
+pub const VERSUS_KEY_MAPS: [KeyMap; 2] = [
+    KeyMap {
+        left: &[KeyCode::A],
+        right: &[KeyCode::D],
+        soft_drop: &[KeyCode::S],
+        hard_drop: &[KeyCode::W],
+        rotate_right: &[KeyCode::E],
+        rotate_left: &[KeyCode::Q],
+        rotate_half: &[KeyCode::R],
+        hold: &[KeyCode::LShift],
+    },
+    KeyMap {
+        left: &[KeyCode::Left],
+        right: &[KeyCode::Right],
+        soft_drop: &[KeyCode::Down],
+        hard_drop: &[KeyCode::Up],
+        rotate_right: &[KeyCode::Period, KeyCode::Numpad2],
+        rotate_left: &[KeyCode::Comma, KeyCode::Numpad1],
+        rotate_half: &[KeyCode::Slash, KeyCode::Numpad3],
+        hold: &[KeyCode::RShift, KeyCode::Numpad0],
+    },
+];
+
+// ゲーム開始時に選べる設定
+#[derive(Resource, Clone)]
+pub struct AppSettings {
+    pub game: GameSettings,
+    // 2 人対戦にするか
+    pub is_versus: bool,
+    // 対戦で右のプレイヤーをコンピューターにするときの強さ
+    pub cpu: Option<AiDifficulty>,
+    // 対戦の攻撃とせり上がりのルール
+    pub garbage_rules: GarbageRules,
+    // モードごとに選んだ乱数生成 選んでいないモードは 7 種 1 巡
+    pub randomizers: HashMap<&'static str, RandomizerType>,
+    // 対戦でのプレイヤーごとの操作の設定 (1P は 1 人用と同じ)
+    pub versus_handling: [Handling; PLAYER_COUNT],
+    pub show_ghost: bool,
+    // ネクストを何個表示するか (1 - 7)
+    pub next_count: usize,
+    // ポーズから戻るときにカウントダウンするか
+    pub resume_countdown: bool,
+    // 起動したらすぐに再生するリプレイ
+    pub replay_path: Option<String>,
+    // リプレイを JSON でも保存するか (確認用)
+    pub save_replay_json: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        return AppSettings {
+            game: GameSettings::default(),
+            is_versus: false,
+            cpu: None,
+            garbage_rules: GarbageRules::default(),
+            randomizers: HashMap::new(),
+            versus_handling: [Handling::default(); PLAYER_COUNT],
+            show_ghost: true,
+            next_count: 5,
+            resume_countdown: true,
+            replay_path: None,
+            save_replay_json: false,
+        };
+    }
+}
+
+impl AppSettings {
+    // 例: tetris --mode sprint --goal-lines 20 (--mode ultra --seconds 180) --rotation ars
+    //     tetris --versus --garbage-rules rules.json --das 8 --p2-das 12 --p2-arr 0
+    //     tetris --cpu hard (--cpu-pps 3 --cpu-depth 2 --cpu-mistakes 0)
+    //     tetris --mode dig --goal-lines 100 --messiness 50 --randomizer tgm3 --lock-delay 20 --lock-reset step --das 8 --arr 0 --seed 42
+    pub fn from_args() -> Self {
+        let mut settings = AppSettings::default();
+        let args: Vec<String> = std::env::args().collect();
+
+        if args.iter().any(|arg| arg == "--no-ghost") {
+            settings.show_ghost = false;
+        }
+        if args.iter().any(|arg| arg == "--no-resume-countdown") {
+            settings.resume_countdown = false;
+        }
+        if args.iter().any(|arg| arg == "--replay-json") {
+            settings.save_replay_json = true;
+        }
+        if args.iter().any(|arg| arg == "--versus") {
+            settings.is_versus = true;
+        }
+        if args.iter().any(|arg| arg == "--partial-lock-out") {
+            settings.game.partial_lock_out = true;
+        }
+
+        let game = &mut settings.game;
+        let mut cpu = None;
+        let mut cpu_pps = None;
+        let mut cpu_depth = None;
+        let mut cpu_mistakes = None;
+        let mut goal_lines = None;
+        let mut seconds = None;
+        let mut messiness = None;
+        let mut randomizer = None;
+        let mut p2_handling = Handling::default();
+        for pair in args.windows(2) {
+            match pair[0].as_str() {
+                "--mode" => match GameMode::from_name(&pair[1]) {
+                    Some(mode) => game.mode = mode,
+                    None => eprintln!("unknown mode: {}", pair[1]),
+                },
+                "--goal-lines" => match pair[1].parse::<i64>() {
+                    Ok(lines) if lines > 0 => goal_lines = Some(lines),
+                    _ => eprintln!("invalid goal lines: {}", pair[1]),
+                },
+                "--seconds" => match pair[1].parse::<i64>() {
+                    Ok(value) if value > 0 => seconds = Some(value),
+                    _ => eprintln!("invalid seconds: {}", pair[1]),
+                },
+                "--messiness" => match pair[1].parse::<i64>() {
+                    Ok(percent) if (0..=100).contains(&percent) => messiness = Some(percent),
+                    _ => eprintln!("invalid messiness: {}", pair[1]),
+                },
+                "--rotation" => match RotationSystemType::from_name(&pair[1]) {
+                    Some(rotation_system) => game.rotation_system = rotation_system,
+                    None => eprintln!("unknown rotation system: {}", pair[1]),
+                },
+                "--randomizer" => match RandomizerType::from_name(&pair[1]) {
+                    Some(value) => randomizer = Some(value),
+                    None => eprintln!("unknown randomizer: {}", pair[1]),
+                },
+                "--lock-delay" => match pair[1].parse() {
+                    Ok(frames) => game.lock_delay = frames,
+                    Err(_) => eprintln!("invalid lock delay: {}", pair[1]),
+                },
+                "--lock-reset" => match LockResetRule::from_name(&pair[1]) {
+                    Some(rule) => game.lock_reset_rule = rule,
+                    None => eprintln!("unknown lock reset rule: {}", pair[1]),
+                },
+                "--lock-reset-limit" => match pair[1].parse() {
+                    Ok(limit) => game.lock_reset_limit = limit,
+                    Err(_) => eprintln!("invalid lock reset limit: {}", pair[1]),
+                },
+                "--level" => parse_number(&pair[1], &mut game.start_level),
+                "--lines-per-level" => parse_number(&pair[1], &mut game.lines_per_level),
+                "--next" => match pair[1].parse::<usize>() {
+                    Ok(count) => settings.next_count = count.clamp(1, MAX_NEXT_COUNT),
+                    Err(_) => eprintln!("invalid next count: {}", pair[1]),
+                },
+                "--cpu" => match AiDifficulty::from_name(&pair[1]) {
+                    Some(difficulty) => cpu = Some(difficulty),
+                    None => eprintln!("unknown cpu difficulty: {}", pair[1]),
+                },
+                "--cpu-pps" => match pair[1].parse::<f64>() {
+                    Ok(pps) if pps >= 0. => cpu_pps = Some(pps),
+                    _ => eprintln!("invalid cpu pps: {}", pair[1]),
+                },
+                "--cpu-depth" => match pair[1].parse::<usize>() {
+                    Ok(depth) if (1..=MAX_SEARCH_DEPTH).contains(&depth) => cpu_depth = Some(depth),
+                    _ => eprintln!("invalid cpu depth: {}", pair[1]),
+                },
+                "--cpu-mistakes" => match pair[1].parse::<i64>() {
+                    Ok(percent) if (0..=100).contains(&percent) => cpu_mistakes = Some(percent),
+                    _ => eprintln!("invalid cpu mistakes: {}", pair[1]),
+                },
+                "--replay" => settings.replay_path = Some(pair[1].clone()),
+                "--garbage-rules" => match GarbageRules::load(Path::new(&pair[1])) {
+                    Ok(rules) => settings.garbage_rules = rules,
+                    Err(error) => eprintln!("failed to load garbage rules {}: {}", pair[1], error),
+                },
+                "--seed" => match pair[1].parse::<u64>() {
+                    Ok(seed) => game.seed = Some(seed),
+                    Err(_) => eprintln!("invalid seed: {}", pair[1]),
+                },
+                "--das" => parse_number(&pair[1], &mut game.handling.das),
+                "--arr" => parse_number(&pair[1], &mut game.handling.arr),
+                "--sdf" => parse_number(&pair[1], &mut game.handling.soft_drop_factor),
+                "--das-cut" => parse_number(&pair[1], &mut game.handling.das_cut),
+                "--p2-das" => parse_number(&pair[1], &mut p2_handling.das),
+                "--p2-arr" => parse_number(&pair[1], &mut p2_handling.arr),
+                "--p2-sdf" => parse_number(&pair[1], &mut p2_handling.soft_drop_factor),
+                "--p2-das-cut" => parse_number(&pair[1], &mut p2_handling.das_cut),
+                _ => {}
+            }
+        }
+        // --mode より前に書かれていても効くように最後に反映する
+        let param = if game.mode.is_param_seconds() {
+            seconds
+        } else {
+            goal_lines
+        };
+        if let Some(param) = param {
+            game.mode = game.mode.with_param(param);
+        }
+        if let Some(messiness) = messiness {
+            game.mode = game.mode.with_messiness(messiness);
+        }
+        // 乱数生成は選んだモードのものとして覚えておく
+        if let Some(randomizer) = randomizer {
+            settings.set_randomizer(randomizer);
+        }
+        settings.versus_handling = [settings.game.handling, p2_handling];
+
+        // 強さを細かく指定しただけなら NORMAL から変える
+        let is_cpu_tuned = cpu_pps.is_some() || cpu_depth.is_some() || cpu_mistakes.is_some();
+        if cpu.is_some() || is_cpu_tuned {
+            let mut difficulty = cpu.unwrap_or_default();
+            difficulty.pps = cpu_pps.unwrap_or(difficulty.pps);
+            difficulty.depth = cpu_depth.unwrap_or(difficulty.depth);
+            difficulty.mistake_percent = cpu_mistakes.unwrap_or(difficulty.mistake_percent);
+            settings.is_versus = true;
+            settings.cpu = Some(difficulty);
+        }
+
+        return settings;
+    }
+
+    // モードを変えたら、そのモードで前に選んだ乱数生成に戻す
+    pub fn set_mode(&mut self, mode: GameMode) {
+        self.game.mode = mode;
+        self.game.randomizer = self.randomizers.get(mode.id()).copied().unwrap_or_default();
+    }
+
+    pub fn set_randomizer(&mut self, randomizer: RandomizerType) {
+        self.game.randomizer = randomizer;
+        self.randomizers.insert(self.game.mode.id(), randomizer);
+    }
+
+    // メニューで 1P, 2P, コンピューターとの対戦 (弱い順) を順番に切り替える
+    pub fn cycle_players(&mut self, is_forward: bool) {
+        let mut options = vec![(false, None), (true, None)];
+        options.extend(
+            AI_PRESETS
+                .iter()
+                .map(|(_, difficulty)| (true, Some(*difficulty))),
+        );
+
+        let current = (self.is_versus, self.cpu.filter(|_| self.is_versus));
+        let len = options.len();
+        let index = match options.iter().position(|option| *option == current) {
+            Some(index) if is_forward => (index + 1) % len,
+            Some(index) => (index + len - 1) % len,
+            // コマンドラインで細かく指定した強さからは 1P に戻す
+            None => 0,
+        };
+        (self.is_versus, self.cpu) = options[index];
+    }
+}
+
+fn parse_number(value: &str, target: &mut i64) {
+    match value.parse() {
+        Ok(frames) if frames >= 0 => *target = frames,
+        _ => eprintln!("invalid number: {}", value),
+    }
+}
+
+#[test]
+fn randomizer_is_kept_per_mode() {
+    let mut settings = AppSettings::default();
+    settings.set_mode(GameMode::from_name("sprint").unwrap());
+    settings.set_randomizer(RandomizerType::Tgm3);
+
+    settings.set_mode(GameMode::Endless);
+    assert_eq!(settings.game.randomizer, RandomizerType::Bag7);
+    settings.set_mode(GameMode::from_name("sprint").unwrap());
+    assert_eq!(settings.game.randomizer, RandomizerType::Tgm3);
 }
@@ -1,2 +1,11 @@
+use tetris_core::event::ScoreEvent;
+
+// 盤面の表示を更新する
 #[derive(Default)]
 pub struct BoardUpdateEvent;
+
+// どのプレイヤーの得点か (1 人用では 0)
+pub struct PlayerScoreEvent {
+    pub player: usize,
+    pub event: ScoreEvent,
+}
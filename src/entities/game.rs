@@ -0,0 +1,152 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::{Deref, DerefMut, DetectChanges, Res, Resource};
+use tetris_core::ai::AiController;
+use tetris_core::game::{Game, Inputs};
+use tetris_core::record::SprintRecord;
+use tetris_core::replay::{ReplayPlayer, ReplayRecorder};
+use tetris_core::versus::{Versus, PLAYER_COUNT};
+
+// 遊んでいるゲーム 無ければ次のカウントダウンで新しく始める
+#[derive(Resource, Deref, DerefMut)]
+pub struct CurrentGame(pub Game);
+
+// 2 人対戦 無ければ対戦を始めるときに新しく作る
+#[derive(Resource, Deref, DerefMut)]
+pub struct CurrentVersus(pub Versus);
+
+// 対戦でコンピューターが動かすプレイヤー (右側) 人同士の対戦では無い
+#[derive(Resource, Deref, DerefMut)]
+pub struct CpuPlayer(pub AiController);
+
+// 描画する側から見たプレイヤーごとのゲーム 1 人用なら 0 番だけ
+#[derive(SystemParam)]
+pub struct Players<'w> {
+    single: Option<Res<'w, CurrentGame>>,
+    versus: Option<Res<'w, CurrentVersus>>,
+}
+
+impl<'w> Players<'w> {
+    pub fn get(&self, player: usize) -> Option<&Game> {
+        if let Some(versus) = &self.versus {
+            return versus.players.get(player);
+        }
+        return self
+            .single
+            .as_ref()
+            .filter(|_| player == 0)
+            .map(|game| &game.0);
+    }
+
+    pub fn is_versus(&self) -> bool {
+        return self.versus.is_some();
+    }
+
+    pub fn is_changed(&self) -> bool {
+        let is_single_changed = self.single.as_ref().is_some_and(|game| game.is_changed());
+        let is_versus_changed = self
+            .versus
+            .as_ref()
+            .is_some_and(|versus| versus.is_changed());
+        return is_single_changed || is_versus_changed;
+    }
+}
+
+// 描画のフレームで読んだ入力を、ゲームを進める固定フレームまでためておく
+// 固定フレームの間に押して離したボタンも 1 回は押したことにする
+#[derive(Resource, Default)]
+pub struct InputBuffer {
+    current: [Inputs; PLAYER_COUNT],
+    latched: [Inputs; PLAYER_COUNT],
+}
+
+impl InputBuffer {
+    pub fn push(&mut self, player: usize, inputs: Inputs) {
+        self.current[player] = inputs;
+        self.latched[player] = self.latched[player].union(inputs);
+    }
+
+    // 次の固定フレームには今押されているボタンだけを持ち越す
+    pub fn take(&mut self, player: usize) -> Inputs {
+        let inputs = self.latched[player];
+        self.latched[player] = self.current[player];
+        return inputs;
+    }
+
+    pub fn clear(&mut self) {
+        *self = InputBuffer::default();
+    }
+}
+
+// 遊んでいるゲームの入力の記録
+#[derive(Resource, Deref, DerefMut)]
+pub struct Recording(pub ReplayRecorder);
+
+// 遊んでいるモードの自己ベスト (スプリント以外や記録が無ければ None)
+#[derive(Resource, Default)]
+pub struct PersonalBest {
+    pub record: Option<SprintRecord>,
+    // このゲームで記録を更新した record は更新前のまま残す
+    pub is_updated: bool,
+}
+
+const REPLAY_SPEEDS: [f64; 5] = [0.25, 0.5, 1., 2., 4.];
+const NORMAL_SPEED_INDEX: usize = 2;
+
+// リプレイの再生状態 進めたゲームは CurrentGame に置いて普段の描画を使う
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub player: ReplayPlayer,
+    pub speed_index: usize,
+    pub is_paused: bool,
+    // 等速より遅いときに 1 フレーム分たまるまで待つ
+    pub progress: f64,
+}
+
+impl ReplayPlayback {
+    pub fn new(player: ReplayPlayer) -> Self {
+        return ReplayPlayback {
+            player: player,
+            speed_index: NORMAL_SPEED_INDEX,
+            is_paused: false,
+            progress: 0.,
+        };
+    }
+
+    pub fn speed(&self) -> f64 {
+        return REPLAY_SPEEDS[self.speed_index];
+    }
+
+    pub fn faster(&mut self) {
+        self.speed_index = (self.speed_index + 1).min(REPLAY_SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed_index = self.speed_index.saturating_sub(1);
+    }
+
+    // このフレームで進めるフレーム数
+    pub fn take_frames(&mut self) -> usize {
+        if self.is_paused {
+            return 0;
+        }
+        self.progress += self.speed();
+        let frames = self.progress.floor();
+        self.progress -= frames;
+        return frames as usize;
+    }
+}
+
+#[test]
+fn tap_between_steps_is_kept_for_one_step() {
+    let mut buffer = InputBuffer::default();
+    let left = Inputs {
+        left: true,
+        ..Default::default()
+    };
+
+    // 固定フレームの間に押して離す
+    buffer.push(0, left);
+    buffer.push(0, Inputs::default());
+    assert_eq!(buffer.take(0), left);
+    assert_eq!(buffer.take(0), Inputs::default());
+}
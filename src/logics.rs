@@ -1,17 +1,85 @@
-use bevy::prelude::{IntoSystemAppConfig, IntoSystemConfig, OnEnter, OnUpdate, Plugin};
+use bevy::prelude::{
+    in_state, App, CoreSchedule, IntoSystemAppConfig, IntoSystemConfig, OnEnter, OnExit, OnUpdate,
+    Plugin,
+};
 
 use crate::entities::config::AppState;
 
-use self::system::{handle_input, initialize_game, tick_board};
+use self::record::save_personal_best;
+use self::replay::{
+    handle_replay_input, load_replay_from_args, save_replay, start_replay, step_replay,
+};
+use self::system::{
+    collect_inputs, end_game, handle_game_input, handle_menu_input, handle_pause_input,
+    handle_results_input, initialize_game, is_new_game, is_playing, pause_on_focus_lost,
+    release_inputs, start_countdown, start_game_over, step_game, tick_countdown, tick_game_over,
+};
+use self::versus::{
+    collect_versus_inputs, end_versus, handle_versus_input, handle_versus_pause_input,
+    handle_versus_results_input, is_new_versus, release_versus_inputs, start_versus, step_versus,
+};
 
+pub mod record;
+pub mod replay;
 pub mod system;
+pub mod versus;
 
 pub struct LogicPlugin;
 
 impl Plugin for LogicPlugin {
-    fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_system(initialize_game.in_schedule(OnEnter(AppState::InGame)))
-            .add_system(handle_input.in_set(OnUpdate(AppState::InGame)))
-            .add_system(tick_board.in_set(OnUpdate(AppState::InGame)));
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(load_replay_from_args)
+            .add_system(end_game.in_schedule(OnEnter(AppState::Menu)))
+            .add_system(handle_menu_input.in_set(OnUpdate(AppState::Menu)))
+            .add_system(
+                initialize_game
+                    .run_if(is_new_game)
+                    .in_schedule(OnEnter(AppState::Countdown)),
+            )
+            .add_system(start_countdown.in_schedule(OnEnter(AppState::Countdown)))
+            // ゲームを進めるのは FixedTime の固定フレームで、キーは描画のフレームごとに読む
+            .add_system(
+                tick_countdown
+                    .in_schedule(CoreSchedule::FixedUpdate)
+                    .run_if(in_state(AppState::Countdown)),
+            )
+            .add_system(collect_inputs.in_set(OnUpdate(AppState::InGame)))
+            .add_system(handle_game_input.in_set(OnUpdate(AppState::InGame)))
+            .add_system(
+                step_game
+                    .in_schedule(CoreSchedule::FixedUpdate)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_system(pause_on_focus_lost.run_if(is_playing))
+            .add_system(handle_pause_input.in_set(OnUpdate(AppState::Paused)))
+            .add_system(release_inputs.in_schedule(OnExit(AppState::Paused)))
+            .add_system(start_game_over.in_schedule(OnEnter(AppState::GameOver)))
+            .add_system(save_replay.in_schedule(OnEnter(AppState::GameOver)))
+            .add_system(save_personal_best.in_schedule(OnEnter(AppState::GameOver)))
+            .add_system(tick_game_over.in_set(OnUpdate(AppState::GameOver)))
+            .add_system(handle_results_input.in_set(OnUpdate(AppState::Results)))
+            .add_system(start_replay.in_schedule(OnEnter(AppState::Replay)))
+            .add_system(handle_replay_input.in_set(OnUpdate(AppState::Replay)))
+            .add_system(
+                step_replay
+                    .in_schedule(CoreSchedule::FixedUpdate)
+                    .run_if(in_state(AppState::Replay)),
+            )
+            .add_system(end_versus.in_schedule(OnEnter(AppState::Menu)))
+            .add_system(
+                start_versus
+                    .run_if(is_new_versus)
+                    .in_schedule(OnEnter(AppState::Versus)),
+            )
+            .add_system(collect_versus_inputs.in_set(OnUpdate(AppState::Versus)))
+            .add_system(handle_versus_input.in_set(OnUpdate(AppState::Versus)))
+            .add_system(
+                step_versus
+                    .in_schedule(CoreSchedule::FixedUpdate)
+                    .run_if(in_state(AppState::Versus)),
+            )
+            .add_system(handle_versus_pause_input.in_set(OnUpdate(AppState::VersusPaused)))
+            .add_system(release_versus_inputs.in_schedule(OnExit(AppState::VersusPaused)))
+            .add_system(handle_versus_results_input.in_set(OnUpdate(AppState::VersusResults)));
     }
 }
@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use tetris_core::{mode::GameMode, record::SprintRecord};
+
+use crate::entities::game::{CurrentGame, PersonalBest};
+
+const RECORD_DIR: &str = "records";
+
+// ライン数ごとに別の記録にする
+fn record_path(lines: i64) -> PathBuf {
+    return PathBuf::from(format!("{}/sprint_{}.json", RECORD_DIR, lines));
+}
+
+pub fn load_personal_best(mode: GameMode) -> PersonalBest {
+    let lines = match mode {
+        GameMode::Sprint { lines } => lines,
+        _ => return PersonalBest::default(),
+    };
+
+    let path = record_path(lines);
+    if !path.exists() {
+        return PersonalBest::default();
+    }
+    return match SprintRecord::load(&path) {
+        Ok(record) => PersonalBest {
+            record: Some(record),
+            is_updated: false,
+        },
+        Err(error) => {
+            eprintln!("failed to load record {}: {}", path.display(), error);
+            PersonalBest::default()
+        }
+    };
+}
+
+// ゴールしたら自己ベストと比べて、速ければ書き換える
+pub fn save_personal_best(game: Res<CurrentGame>, mut best: ResMut<PersonalBest>) {
+    let record = match SprintRecord::from_game(&game) {
+        Some(record) => record,
+        None => return,
+    };
+    if best
+        .record
+        .as_ref()
+        .is_some_and(|best| !record.is_better_than(best))
+    {
+        return;
+    }
+
+    if let Err(error) = std::fs::create_dir_all(RECORD_DIR) {
+        eprintln!("failed to create {}: {}", RECORD_DIR, error);
+        return;
+    }
+    let path = record_path(record.lines);
+    match record.save(&path) {
+        Ok(()) => best.is_updated = true,
+        Err(error) => eprintln!("failed to save record {}: {}", path.display(), error),
+    }
+}
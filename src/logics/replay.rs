@@ -0,0 +1,146 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use tetris_core::{
+    config::FRAMES_PER_SECOND,
+    event::{GameEvent, LockEvent},
+    replay::{Replay, ReplayPlayer},
+};
+
+use crate::entities::{
+    config::{AppSettings, AppState},
+    event::{BoardUpdateEvent, PlayerScoreEvent},
+    game::{CurrentGame, PersonalBest, Recording, ReplayPlayback},
+};
+
+use super::record::load_personal_best;
+
+const REPLAY_DIR: &str = "replays";
+// 左右キーで飛ばすフレーム数
+const SEEK_FRAMES: u32 = 5 * FRAMES_PER_SECOND as u32;
+
+// --replay で指定されたら起動してすぐに再生する
+pub fn load_replay_from_args(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<AppState>>,
+    settings: Res<AppSettings>,
+) {
+    let path = match &settings.replay_path {
+        Some(path) => path,
+        None => return,
+    };
+
+    match Replay::load(Path::new(path)) {
+        Ok(replay) => {
+            commands.insert_resource(ReplayPlayback::new(ReplayPlayer::new(replay)));
+            next_state.set(AppState::Replay);
+        }
+        Err(error) => eprintln!("failed to load replay {}: {}", path, error),
+    }
+}
+
+// ゲームオーバーになったら replays/ に保存する
+pub fn save_replay(recording: Res<Recording>, settings: Res<AppSettings>) {
+    if let Err(error) = std::fs::create_dir_all(REPLAY_DIR) {
+        eprintln!("failed to create {}: {}", REPLAY_DIR, error);
+        return;
+    }
+
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let mut paths = vec![PathBuf::from(format!("{}/{}.ttr", REPLAY_DIR, seconds))];
+    if settings.save_replay_json {
+        paths.push(PathBuf::from(format!("{}/{}.json", REPLAY_DIR, seconds)));
+    }
+
+    for path in paths {
+        match recording.replay.save(&path) {
+            Ok(()) => println!("saved replay: {}", path.display()),
+            Err(error) => eprintln!("failed to save replay {}: {}", path.display(), error),
+        }
+    }
+}
+
+pub fn start_replay(
+    mut commands: Commands,
+    mut event_w: EventWriter<BoardUpdateEvent>,
+    mut playback: ResMut<ReplayPlayback>,
+    best: Option<Res<PersonalBest>>,
+) {
+    let game = playback.player.start();
+    // 起動時に読み込んだリプレイではスプリットを比べる記録がまだ無い
+    if best.is_none() {
+        commands.insert_resource(load_personal_best(game.settings.mode));
+    }
+    commands.insert_resource(CurrentGame(game));
+    event_w.send_default();
+}
+
+// 再生の操作は描画のフレームごとに受け付ける
+pub fn handle_replay_input(
+    mut next_state: ResMut<NextState<AppState>>,
+    mut event_w: EventWriter<BoardUpdateEvent>,
+    kc: Res<Input<KeyCode>>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut game: ResMut<CurrentGame>,
+) {
+    if kc.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+        return;
+    }
+
+    if kc.just_pressed(KeyCode::Space) {
+        playback.is_paused = !playback.is_paused;
+    }
+    if kc.just_pressed(KeyCode::Up) {
+        playback.faster();
+    } else if kc.just_pressed(KeyCode::Down) {
+        playback.slower();
+    }
+
+    // 飛ばした先ではイベントを出さずに盤面だけ描き直す
+    let frame = playback.player.frame;
+    let seek_to = if kc.just_pressed(KeyCode::Home) {
+        Some(0)
+    } else if kc.just_pressed(KeyCode::Left) {
+        Some(frame.saturating_sub(SEEK_FRAMES))
+    } else if kc.just_pressed(KeyCode::Right) {
+        Some(frame + SEEK_FRAMES)
+    } else {
+        None
+    };
+    if let Some(frame) = seek_to {
+        game.0 = playback.player.seek(frame);
+        event_w.send_default();
+    }
+}
+
+// 遊んだときと同じ固定フレームで進める
+pub fn step_replay(
+    mut event_w: EventWriter<BoardUpdateEvent>,
+    mut lock_w: EventWriter<LockEvent>,
+    mut score_w: EventWriter<PlayerScoreEvent>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut game: ResMut<CurrentGame>,
+) {
+    let mut is_updated = false;
+    for _ in 0..playback.take_frames() {
+        for event in playback.player.step(&mut game) {
+            match event {
+                GameEvent::BoardUpdate => is_updated = true,
+                GameEvent::Lock(event) => lock_w.send(event),
+                GameEvent::Score(event) => score_w.send(PlayerScoreEvent {
+                    player: 0,
+                    event: event,
+                }),
+                GameEvent::Attack(_) | GameEvent::GameOver(_) => {}
+            }
+        }
+    }
+
+    if is_updated {
+        event_w.send_default();
+    }
+}
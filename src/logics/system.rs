@@ -1,107 +1,397 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, window::WindowFocused};
+use tetris_core::{
+    config::GameSettings,
+    event::{GameEvent, GameOverEvent, LockEvent},
+    game::{Game, Inputs},
+    replay::{ReplayPlayer, ReplayRecorder},
+};
 
+use super::record::load_personal_best;
 use crate::entities::{
-    config::AppState,
-    counter::FrameCounter,
-    event::BoardUpdateEvent,
-    tetris::{GameBoard, MoveDirection, StockTetrimono, TetrimonoQueue},
+    config::{
+        AppSettings, AppState, KeyMap, MenuCursor, MenuItem, COUNTDOWN_FRAMES, GAME_OVER_FRAMES,
+        SINGLE_KEY_MAP,
+    },
+    counter::StateTimer,
+    event::{BoardUpdateEvent, PlayerScoreEvent},
+    game::{CurrentGame, InputBuffer, Recording, ReplayPlayback},
 };
 
-pub fn initialize_game(mut commands: Commands) {
-    let mut queue = TetrimonoQueue::default();
-
-    while !queue.is_enough_mono() {
-        queue.add_set(true);
-    }
+// ゲームが無ければ新しく始める (ポーズからの再開では作り直さない)
+pub fn is_new_game(game: Option<Res<CurrentGame>>) -> bool {
+    return game.is_none();
+}
 
-    let mut game_board = GameBoard::default();
-    game_board.init_tetrimono(queue.dequeue());
+// 操作しているゲームがある状態
+pub fn is_playing(state: Res<State<AppState>>) -> bool {
+    return matches!(
+        state.0,
+        AppState::InGame | AppState::Countdown | AppState::Versus
+    );
+}
 
-    commands.insert_resource(game_board);
-    commands.insert_resource(queue);
-    commands.insert_resource(StockTetrimono::default());
-    commands.insert_resource(FrameCounter::default());
+// ボードを表示・更新する状態
+pub fn is_showing_board(state: Res<State<AppState>>) -> bool {
+    return matches!(
+        state.0,
+        AppState::InGame | AppState::Countdown | AppState::Replay | AppState::Versus
+    );
 }
 
-pub fn tick_board(
-    mut next_state: ResMut<NextState<AppState>>,
+pub fn initialize_game(
+    mut commands: Commands,
     mut event_w: EventWriter<BoardUpdateEvent>,
-    mut counter: ResMut<FrameCounter>,
-    mut mono_queue: ResMut<TetrimonoQueue>,
-    mut game_board: ResMut<GameBoard>,
+    mut buffer: ResMut<InputBuffer>,
+    settings: Res<AppSettings>,
 ) {
-    counter.tick();
+    buffer.clear();
+    let game = Game::new(settings.game);
+    commands.insert_resource(load_personal_best(settings.game.mode));
+    commands.insert_resource(Recording(ReplayRecorder::new(&game)));
+    commands.insert_resource(CurrentGame(game));
+    event_w.send_default();
+}
+
+// 次のゲームのために片付ける
+pub fn end_game(mut commands: Commands) {
+    commands.remove_resource::<CurrentGame>();
+}
 
-    if !counter.is_finished() {
+pub fn handle_menu_input(
+    mut next_state: ResMut<NextState<AppState>>,
+    kc: Res<Input<KeyCode>>,
+    mut cursor: ResMut<MenuCursor>,
+    mut settings: ResMut<AppSettings>,
+) {
+    if kc.just_pressed(KeyCode::Return) {
+        if settings.is_versus {
+            next_state.set(AppState::Versus);
+        } else {
+            next_state.set(AppState::Countdown);
+        }
         return;
     }
-    event_w.send_default();
 
-    if game_board.simulate_move(MoveDirection::Down) {
-        // can drop
-        game_board.apply_move(MoveDirection::Down);
-    } else {
-        // cannot drop
-        game_board.write_tetrimono();
-        game_board.init_tetrimono(mono_queue.dequeue());
+    if kc.just_pressed(KeyCode::Up) {
+        cursor.up();
+    } else if kc.just_pressed(KeyCode::Down) {
+        cursor.down();
+    }
 
-        if game_board.is_game_over() {
-            next_state.set(AppState::InGame);
-        }
+    let is_left = kc.just_pressed(KeyCode::Left);
+    let is_right = kc.just_pressed(KeyCode::Right);
+    if cursor.item() == MenuItem::Players && (is_left || is_right) {
+        settings.cycle_players(is_right);
+    }
 
-        let clear_count = game_board.clear_lines();
+    let GameSettings {
+        mode,
+        rotation_system,
+        randomizer,
+        ..
+    } = settings.game;
+    match cursor.item() {
+        MenuItem::Mode if is_left => settings.set_mode(mode.prev()),
+        MenuItem::Mode if is_right => settings.set_mode(mode.next()),
+        MenuItem::Rotation if is_left => settings.game.rotation_system = rotation_system.prev(),
+        MenuItem::Rotation if is_right => settings.game.rotation_system = rotation_system.next(),
+        // 乱数生成はモードごとに覚えておく
+        MenuItem::Randomizer if is_left => settings.set_randomizer(randomizer.prev()),
+        MenuItem::Randomizer if is_right => settings.set_randomizer(randomizer.next()),
+        _ => {}
+    }
 
-        while !mono_queue.is_enough_mono() {
-            mono_queue.add_set(true);
+    let game = &mut settings.game;
+
+    // シードはどの行からでも数字キーで入力できる
+    if kc.just_pressed(KeyCode::Back) {
+        // 1 桁ずつ消して、全部消したらランダムに戻す
+        game.seed = game.seed.map(|seed| seed / 10).filter(|seed| *seed > 0);
+    } else if let Some(digit) = kc.get_just_pressed().find_map(|key| key_digit(*key)) {
+        let seed = game.seed.unwrap_or(0);
+        if seed < MAX_MENU_SEED / 10 {
+            game.seed = Some(seed * 10 + digit);
         }
     }
+}
+
+// メニューで入力できるシードの上限 (10 桁まで)
+const MAX_MENU_SEED: u64 = 10_000_000_000;
+
+fn key_digit(key: KeyCode) -> Option<u64> {
+    let keys = [
+        (KeyCode::Key0, KeyCode::Numpad0),
+        (KeyCode::Key1, KeyCode::Numpad1),
+        (KeyCode::Key2, KeyCode::Numpad2),
+        (KeyCode::Key3, KeyCode::Numpad3),
+        (KeyCode::Key4, KeyCode::Numpad4),
+        (KeyCode::Key5, KeyCode::Numpad5),
+        (KeyCode::Key6, KeyCode::Numpad6),
+        (KeyCode::Key7, KeyCode::Numpad7),
+        (KeyCode::Key8, KeyCode::Numpad8),
+        (KeyCode::Key9, KeyCode::Numpad9),
+    ];
+    return keys
+        .iter()
+        .position(|(main, numpad)| key == *main || key == *numpad)
+        .map(|digit| digit as u64);
+}
+
+pub fn start_countdown(mut commands: Commands) {
+    commands.insert_resource(StateTimer::new(COUNTDOWN_FRAMES));
+}
+
+pub fn tick_countdown(mut next_state: ResMut<NextState<AppState>>, mut timer: ResMut<StateTimer>) {
+    timer.tick();
+    if timer.is_finished() {
+        next_state.set(AppState::InGame);
+    }
+}
 
-    counter.reset();
+pub fn start_game_over(mut commands: Commands) {
+    commands.insert_resource(StateTimer::new(GAME_OVER_FRAMES));
 }
 
-// 更新したらイベントを出そう
+pub fn tick_game_over(
+    mut next_state: ResMut<NextState<AppState>>,
+    kc: Res<Input<KeyCode>>,
+    mut timer: ResMut<StateTimer>,
+) {
+    timer.tick();
+    if timer.is_finished() || kc.just_pressed(KeyCode::Return) {
+        next_state.set(AppState::Results);
+    }
+}
 
-pub fn handle_input(
+pub fn handle_results_input(
+    mut commands: Commands,
     mut next_state: ResMut<NextState<AppState>>,
-    mut event_w: EventWriter<BoardUpdateEvent>,
     kc: Res<Input<KeyCode>>,
-    // input_counter: Local<(i64, i64)>,
-    // mut stock_mono: ResMut<StockTetrimono>,
-    // mut mono_queue: ResMut<TetrimonoQueue>,
-    mut game_board: ResMut<GameBoard>,
-    mut counter: ResMut<FrameCounter>,
+    recording: Res<Recording>,
 ) {
-    if kc.just_pressed(KeyCode::Left) {
-        if !game_board.simulate_move(MoveDirection::Left) {
-            return;
-        }
-        game_board.apply_move(MoveDirection::Left);
-    } else if kc.just_pressed(KeyCode::Right) {
-        if !game_board.simulate_move(MoveDirection::Right) {
-            return;
-        }
-        game_board.apply_move(MoveDirection::Right);
-    } else if kc.just_pressed(KeyCode::Up) {
-        if game_board.try_rotate(true) {
-            return;
-        }
-    } else if kc.just_pressed(KeyCode::Space) {
-        while game_board.simulate_move(MoveDirection::Down) {
-            game_board.apply_move(MoveDirection::Down);
+    if kc.any_just_pressed([KeyCode::Return, KeyCode::R]) {
+        // もう一度
+        commands.remove_resource::<CurrentGame>();
+        next_state.set(AppState::Countdown);
+    } else if kc.just_pressed(KeyCode::V) {
+        // 今のゲームを見直す
+        let player = ReplayPlayer::new(recording.replay.clone());
+        commands.insert_resource(ReplayPlayback::new(player));
+        next_state.set(AppState::Replay);
+    } else if kc.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+// ポーズ中は InGame で動く固定フレームのシステムが止まるので時計も落下も進まない
+pub fn handle_pause_input(
+    mut next_state: ResMut<NextState<AppState>>,
+    kc: Res<Input<KeyCode>>,
+    settings: Res<AppSettings>,
+) {
+    if kc.any_just_pressed([KeyCode::Escape, KeyCode::P]) {
+        if settings.resume_countdown {
+            next_state.set(AppState::Countdown);
+        } else {
+            next_state.set(AppState::InGame);
         }
-        // 次の時間経過ですぐに処理させる
-        counter.force_finish();
-    } else if kc.just_pressed(KeyCode::Down) {
-        if !game_board.simulate_move(MoveDirection::Down) {
-            return;
+    } else if kc.just_pressed(KeyCode::Q) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+// 再開したときに押しっぱなしの扱いを持ち越さない
+pub fn release_inputs(
+    mut game: ResMut<CurrentGame>,
+    mut recording: ResMut<Recording>,
+    mut buffer: ResMut<InputBuffer>,
+) {
+    buffer.clear();
+    game.release_inputs();
+    recording.release();
+}
+
+// ウィンドウのフォーカスが外れたら自動でポーズする
+pub fn pause_on_focus_lost(
+    mut next_state: ResMut<NextState<AppState>>,
+    mut focus_r: EventReader<WindowFocused>,
+    state: Res<State<AppState>>,
+) {
+    if focus_r.iter().any(|event| !event.focused) {
+        let paused = match state.0 {
+            AppState::Versus => AppState::VersusPaused,
+            _ => AppState::Paused,
+        };
+        next_state.set(paused);
+    }
+}
+
+// キーボードの状態をゲームの入力に変換する
+pub fn read_inputs(kc: &Input<KeyCode>, map: &KeyMap) -> Inputs {
+    let pressed = |keys: &[KeyCode]| kc.any_pressed(keys.iter().copied());
+    return Inputs {
+        left: pressed(map.left),
+        right: pressed(map.right),
+        soft_drop: pressed(map.soft_drop),
+        hard_drop: pressed(map.hard_drop),
+        rotate_right: pressed(map.rotate_right),
+        rotate_left: pressed(map.rotate_left),
+        rotate_half: pressed(map.rotate_half),
+        hold: pressed(map.hold),
+    };
+}
+
+// ゲームパッドは十字キーで移動、上でハードドロップ、右下の 3 ボタンで回転、肩ボタンでホールド
+pub fn read_gamepad(buttons: &Input<GamepadButton>, gamepad: Gamepad) -> Inputs {
+    let pressed = |types: &[GamepadButtonType]| {
+        types
+            .iter()
+            .any(|typ| buttons.pressed(GamepadButton::new(gamepad, *typ)))
+    };
+    return Inputs {
+        left: pressed(&[GamepadButtonType::DPadLeft]),
+        right: pressed(&[GamepadButtonType::DPadRight]),
+        soft_drop: pressed(&[GamepadButtonType::DPadDown]),
+        hard_drop: pressed(&[GamepadButtonType::DPadUp]),
+        rotate_right: pressed(&[GamepadButtonType::East]),
+        rotate_left: pressed(&[GamepadButtonType::South]),
+        rotate_half: pressed(&[GamepadButtonType::North]),
+        hold: pressed(&[
+            GamepadButtonType::LeftTrigger,
+            GamepadButtonType::RightTrigger,
+        ]),
+    };
+}
+
+// 描画のフレームごとにキーボードとゲームパッドを読む
+pub fn collect_inputs(
+    kc: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    mut buffer: ResMut<InputBuffer>,
+) {
+    let mut inputs = read_inputs(&kc, &SINGLE_KEY_MAP);
+    if let Some(gamepad) = gamepads.iter().next() {
+        inputs = inputs.union(read_gamepad(&buttons, gamepad));
+    }
+    buffer.push(0, inputs);
+}
+
+// やり直しとポーズは固定フレームを待たずに受け付ける
+pub fn handle_game_input(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<AppState>>,
+    kc: Res<Input<KeyCode>>,
+) {
+    if kc.just_pressed(KeyCode::R) {
+        // 最初からやり直す
+        commands.remove_resource::<CurrentGame>();
+        next_state.set(AppState::Countdown);
+    } else if kc.any_just_pressed([KeyCode::Escape, KeyCode::P]) {
+        next_state.set(AppState::Paused);
+    }
+}
+
+// 固定フレームで 1 フレーム進めて、起きたことを Bevy のイベントとして流す
+// 描画のフレームレートに関係なく 1 秒に FRAMES_PER_SECOND 回だけ進む
+pub fn step_game(
+    mut next_state: ResMut<NextState<AppState>>,
+    mut event_w: EventWriter<BoardUpdateEvent>,
+    mut lock_w: EventWriter<LockEvent>,
+    mut score_w: EventWriter<PlayerScoreEvent>,
+    mut game_over_w: EventWriter<GameOverEvent>,
+    mut buffer: ResMut<InputBuffer>,
+    mut game: ResMut<CurrentGame>,
+    mut recording: ResMut<Recording>,
+) {
+    let mut is_updated = false;
+    let inputs = buffer.take(0);
+    recording.record(inputs);
+    for event in game.step(inputs) {
+        match event {
+            GameEvent::BoardUpdate => is_updated = true,
+            GameEvent::Lock(event) => lock_w.send(event),
+            GameEvent::Score(event) => score_w.send(PlayerScoreEvent {
+                player: 0,
+                event: event,
+            }),
+            // 1 人用では送る相手がいない
+            GameEvent::Attack(_) => {}
+            GameEvent::GameOver(event) => {
+                game_over_w.send(event);
+                next_state.set(AppState::GameOver);
+            }
         }
-        game_board.apply_move(MoveDirection::Down);
-    } else if kc.just_pressed(KeyCode::R) {
-        next_state.set(AppState::InGame);
     }
 
-    event_w.send_default();
+    if is_updated {
+        event_w.send_default();
+    }
 }
 
 #[test]
 fn larger_can_hold_smaller() {}
+
+#[test]
+fn game_clock_counts_fixed_steps_not_render_frames() {
+    use tetris_core::config::FRAMES_PER_SECOND;
+    use tetris_core::counter::format_frames_millis;
+
+    let game = Game::new(GameSettings {
+        seed: Some(1),
+        ..Default::default()
+    });
+    let mut app = App::new();
+    app.add_state::<AppState>()
+        .add_event::<BoardUpdateEvent>()
+        .add_event::<LockEvent>()
+        .add_event::<PlayerScoreEvent>()
+        .add_event::<GameOverEvent>()
+        .insert_resource(Input::<KeyCode>::default())
+        .insert_resource(Gamepads::default())
+        .insert_resource(Input::<GamepadButton>::default())
+        .insert_resource(InputBuffer::default())
+        .insert_resource(Recording(ReplayRecorder::new(&game)))
+        .insert_resource(CurrentGame(game))
+        .insert_resource(NextState(Some(AppState::InGame)))
+        .add_system(collect_inputs.in_set(OnUpdate(AppState::InGame)))
+        .add_system(
+            step_game
+                .in_schedule(CoreSchedule::FixedUpdate)
+                .run_if(in_state(AppState::InGame)),
+        );
+
+    // 描画のフレームが何回あっても、固定フレーム 2 秒分なら 2 秒と表示する
+    for render_frames in [1, 3] {
+        app.world.resource_mut::<CurrentGame>().clock = Default::default();
+        for _ in 0..(2 * FRAMES_PER_SECOND) {
+            for _ in 0..render_frames {
+                app.update();
+            }
+            app.world.run_schedule(CoreSchedule::FixedUpdate);
+        }
+        let frames = app.world.resource::<CurrentGame>().clock.frames;
+        assert_eq!(format_frames_millis(frames), "00:02.000");
+    }
+}
+
+#[test]
+fn losing_focus_pauses_versus() {
+    let mut app = App::new();
+    app.add_state::<AppState>()
+        .add_event::<WindowFocused>()
+        .insert_resource(NextState(Some(AppState::Versus)))
+        .add_system(pause_on_focus_lost.run_if(is_playing));
+    app.update();
+
+    app.world.send_event(WindowFocused {
+        window: Entity::PLACEHOLDER,
+        focused: false,
+    });
+    app.update();
+    app.update();
+    assert_eq!(
+        app.world.resource::<State<AppState>>().0,
+        AppState::VersusPaused
+    );
+}
@@ -0,0 +1,210 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use tetris_core::{
+    ai::AiController,
+    event::GameEvent,
+    game::Inputs,
+    versus::{Versus, PLAYER_COUNT},
+};
+
+use super::system::{read_gamepad, read_inputs};
+use crate::entities::{
+    config::{
+        AppSettings, AppState, COUNTDOWN_FRAMES, SINGLE_KEY_MAP, VERSUS_KEY_MAPS,
+        VERSUS_WINDOW_WIDTH, WINDOW_HEIGHT, WINDOW_WIDTH,
+    },
+    counter::StateTimer,
+    event::{BoardUpdateEvent, PlayerScoreEvent},
+    game::{CpuPlayer, CurrentVersus, InputBuffer},
+};
+
+// 対戦が無ければ新しく始める (ポーズからの再開では作り直さない)
+pub fn is_new_versus(versus: Option<Res<CurrentVersus>>) -> bool {
+    return versus.is_none();
+}
+
+// ポーズから再開したときにカウントダウンをやり直すか
+pub fn is_counting_down(timer: Option<Res<StateTimer>>) -> bool {
+    return timer.is_some_and(|timer| !timer.is_finished());
+}
+
+// 対戦は毎回同じ設定で作り直す (再戦もここを通る)
+// 操作の設定だけはプレイヤーごとに変える
+pub fn start_versus(
+    mut commands: Commands,
+    mut event_w: EventWriter<BoardUpdateEvent>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut buffer: ResMut<InputBuffer>,
+    settings: Res<AppSettings>,
+) {
+    buffer.clear();
+    let mut versus = Versus::new(settings.game, &settings.garbage_rules);
+    for (game, handling) in versus.players.iter_mut().zip(settings.versus_handling) {
+        game.settings.handling = handling;
+    }
+    match settings.cpu {
+        Some(difficulty) => {
+            let rules = settings.garbage_rules.clone();
+            let ai = AiController::new(difficulty, rules, versus.players[1].seed);
+            commands.insert_resource(CpuPlayer(ai));
+        }
+        None => commands.remove_resource::<CpuPlayer>(),
+    }
+    commands.insert_resource(CurrentVersus(versus));
+    commands.insert_resource(StateTimer::new(COUNTDOWN_FRAMES));
+    if let Ok(mut window) = windows.get_single_mut() {
+        window.resolution.set(VERSUS_WINDOW_WIDTH, WINDOW_HEIGHT);
+    }
+    event_w.send_default();
+}
+
+// メニューに戻ったら 1 人用の大きさに戻す
+pub fn end_versus(
+    mut commands: Commands,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    versus: Option<Res<CurrentVersus>>,
+) {
+    if versus.is_none() {
+        return;
+    }
+    commands.remove_resource::<CurrentVersus>();
+    commands.remove_resource::<CpuPlayer>();
+    if let Ok(mut window) = windows.get_single_mut() {
+        window.resolution.set(WINDOW_WIDTH, WINDOW_HEIGHT);
+    }
+}
+
+// 左のプレイヤーから順に 1 台目、2 台目のゲームパッドを割り当てる
+// コンピューターと対戦するときは、人は 1 人用と同じキーで操作する
+pub fn collect_versus_inputs(
+    kc: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    mut buffer: ResMut<InputBuffer>,
+    cpu: Option<Res<CpuPlayer>>,
+) {
+    let mut inputs = match cpu {
+        Some(_) => [read_inputs(&kc, &SINGLE_KEY_MAP), Inputs::default()],
+        None => [
+            read_inputs(&kc, &VERSUS_KEY_MAPS[0]),
+            read_inputs(&kc, &VERSUS_KEY_MAPS[1]),
+        ],
+    };
+    for (player, gamepad) in gamepads.iter().take(PLAYER_COUNT).enumerate() {
+        inputs[player] = inputs[player].union(read_gamepad(&buttons, gamepad));
+    }
+    for (player, inputs) in inputs.into_iter().enumerate() {
+        buffer.push(player, inputs);
+    }
+}
+
+// どちらのプレイヤーもキーボードかゲームパッドのスタートでポーズできる
+fn is_pause_pressed(
+    kc: &Input<KeyCode>,
+    gamepads: &Gamepads,
+    buttons: &Input<GamepadButton>,
+) -> bool {
+    let is_start_pressed = gamepads
+        .iter()
+        .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::Start)));
+    return kc.any_just_pressed([KeyCode::Escape, KeyCode::P]) || is_start_pressed;
+}
+
+pub fn handle_versus_input(
+    mut next_state: ResMut<NextState<AppState>>,
+    kc: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+) {
+    if is_pause_pressed(&kc, &gamepads, &buttons) {
+        next_state.set(AppState::VersusPaused);
+    }
+}
+
+pub fn handle_versus_pause_input(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<AppState>>,
+    kc: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    settings: Res<AppSettings>,
+) {
+    if is_pause_pressed(&kc, &gamepads, &buttons) {
+        // 2 人とも構え直せるようにカウントダウンからやり直す
+        if settings.resume_countdown {
+            commands.insert_resource(StateTimer::new(COUNTDOWN_FRAMES));
+        }
+        next_state.set(AppState::Versus);
+    } else if kc.just_pressed(KeyCode::Q) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+// 再開したときに押しっぱなしの扱いを持ち越さない
+pub fn release_versus_inputs(mut versus: ResMut<CurrentVersus>, mut buffer: ResMut<InputBuffer>) {
+    buffer.clear();
+    for game in versus.players.iter_mut() {
+        game.release_inputs();
+    }
+}
+
+// 固定フレームで 2 人分進める コンピューターも同じフレームで入力を決める
+pub fn step_versus(
+    mut next_state: ResMut<NextState<AppState>>,
+    mut event_w: EventWriter<BoardUpdateEvent>,
+    mut score_w: EventWriter<PlayerScoreEvent>,
+    mut buffer: ResMut<InputBuffer>,
+    mut timer: ResMut<StateTimer>,
+    mut versus: ResMut<CurrentVersus>,
+    mut cpu: Option<ResMut<CpuPlayer>>,
+) {
+    // カウントダウン中に押したボタンは持ち越さない
+    let mut inputs = [buffer.take(0), buffer.take(1)];
+
+    // 2 人とも同じタイミングで始める
+    if !timer.is_finished() {
+        timer.tick();
+        return;
+    }
+
+    if let Some(cpu) = cpu.as_deref_mut() {
+        inputs[1] = cpu.next_inputs(&versus.players[1]);
+    }
+    let mut is_updated = false;
+    let events = versus.step(inputs);
+    for (player, player_events) in events.into_iter().enumerate() {
+        for event in player_events {
+            match event {
+                GameEvent::BoardUpdate => is_updated = true,
+                GameEvent::Score(event) => score_w.send(PlayerScoreEvent {
+                    player: player,
+                    event: event,
+                }),
+                // 攻撃は Versus の中で相手に渡しているので、メーターを描き直すだけ
+                GameEvent::Attack(_) => is_updated = true,
+                GameEvent::Lock(_) | GameEvent::GameOver(_) => {}
+            }
+        }
+    }
+
+    if is_updated {
+        event_w.send_default();
+    }
+
+    if versus.is_over() {
+        next_state.set(AppState::VersusResults);
+    }
+}
+
+pub fn handle_versus_results_input(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<AppState>>,
+    kc: Res<Input<KeyCode>>,
+) {
+    if kc.just_pressed(KeyCode::Return) {
+        // 再戦
+        commands.remove_resource::<CurrentVersus>();
+        next_state.set(AppState::Versus);
+    } else if kc.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+    }
+}
@@ -1,11 +1,28 @@
+// return の明示やフィールド名の重複はこのリポジトリの書き方として許容する
+// Bevy のシステムは引数が多くなりがちなので too_many_arguments も許容する
+#![allow(
+    clippy::needless_return,
+    clippy::redundant_field_names,
+    clippy::needless_range_loop,
+    clippy::too_many_arguments
+)]
+
 use bevy::{
     // diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
     prelude::*,
     window::{ExitCondition, WindowResolution},
 };
-use entities::{config::AppState, event::BoardUpdateEvent, tetris::GameBoard};
+use entities::{
+    config::{AppSettings, AppState, MenuCursor, WINDOW_HEIGHT, WINDOW_WIDTH},
+    event::{BoardUpdateEvent, PlayerScoreEvent},
+    game::InputBuffer,
+};
 use logics::LogicPlugin;
 use presents::RenderPlugin;
+use tetris_core::{
+    config::FRAMES_PER_SECOND,
+    event::{GameOverEvent, LockEvent},
+};
 
 mod entities;
 mod logics;
@@ -15,16 +32,22 @@ fn main() {
     App::new()
         .add_state::<AppState>()
         .add_event::<BoardUpdateEvent>()
-        .insert_resource(GameBoard::default())
+        .add_event::<LockEvent>()
+        .add_event::<PlayerScoreEvent>()
+        .add_event::<GameOverEvent>()
+        .insert_resource(AppSettings::from_args())
+        .insert_resource(MenuCursor::default())
+        .insert_resource(InputBuffer::default())
         // .add_plugin(LogDiagnosticsPlugin::default())
         // .add_plugin(FrameTimeDiagnosticsPlugin::default())
-        .insert_resource(FixedTime::new_from_secs(1. / 60.))
+        // ゲームは 1 秒に FRAMES_PER_SECOND 回だけ進める
+        .insert_resource(FixedTime::new_from_secs(1. / FRAMES_PER_SECOND as f32))
         .insert_resource(ClearColor(Color::BLACK))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: String::from("Tetris"),
                 resizable: true,
-                resolution: WindowResolution::new(500., 676.),
+                resolution: WindowResolution::new(WINDOW_WIDTH, WINDOW_HEIGHT),
                 ..Default::default()
             }),
             exit_condition: ExitCondition::OnAllClosed,
@@ -36,7 +59,6 @@ fn main() {
         .run();
 }
 
-fn setup_system(mut commands: Commands, mut state: ResMut<NextState<AppState>>) {
+fn setup_system(mut commands: Commands) {
     commands.spawn(Camera2dBundle::default());
-    state.set(AppState::InGame);
 }
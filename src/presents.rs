@@ -1,17 +1,110 @@
-use bevy::prelude::{IntoSystemAppConfig, IntoSystemConfig, OnEnter, OnUpdate, Plugin};
+use bevy::prelude::{
+    App, IntoSystemAppConfig, IntoSystemAppConfigs, IntoSystemConfig, IntoSystemConfigs, OnEnter,
+    OnExit, OnUpdate, Plugin,
+};
 
 use crate::entities::config::AppState;
+use crate::logics::system::{is_new_game, is_showing_board};
+use crate::logics::versus::{is_counting_down, is_new_versus};
 
-use self::system::{create_board, update_board};
+use self::components::{
+    CountdownScreen, GameOverScreen, GameScreen, MenuScreen, PauseScreen, ReplayScreen,
+    ResultsScreen, VersusResultsScreen,
+};
+use self::font::render_dot_text;
+use self::screen::{
+    create_countdown, create_game_over, create_menu, create_pause, create_replay_hud,
+    create_results, create_versus_countdown, create_versus_pause, create_versus_results,
+    despawn_screen, hide_board, show_board, update_countdown, update_menu, update_replay_hud,
+    update_versus_countdown,
+};
+use self::system::{
+    create_game_screen, create_versus_screen, update_action_text, update_board,
+    update_garbage_meter, update_hud, update_next, update_stock,
+};
 
 pub mod components;
+pub mod font;
+pub mod screen;
 pub mod system;
 
 pub struct RenderPlugin;
 
 impl Plugin for RenderPlugin {
-    fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_system(create_board.in_schedule(OnEnter(AppState::InGame)))
-            .add_system(update_board.in_set(OnUpdate(AppState::InGame)));
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            (despawn_screen::<GameScreen>, create_menu).in_schedule(OnEnter(AppState::Menu)),
+        )
+        .add_system(update_menu.in_set(OnUpdate(AppState::Menu)))
+        .add_system(despawn_screen::<MenuScreen>.in_schedule(OnExit(AppState::Menu)))
+        // ポーズからの再開ではボードを作り直さない
+        .add_systems(
+            (despawn_screen::<GameScreen>, create_game_screen)
+                .chain()
+                .distributive_run_if(is_new_game)
+                .in_schedule(OnEnter(AppState::Countdown)),
+        )
+        .add_system(create_countdown.in_schedule(OnEnter(AppState::Countdown)))
+        .add_system(update_countdown.in_set(OnUpdate(AppState::Countdown)))
+        .add_system(despawn_screen::<CountdownScreen>.in_schedule(OnExit(AppState::Countdown)))
+        .add_systems(
+            (
+                update_board,
+                update_stock,
+                update_next,
+                update_hud,
+                update_garbage_meter,
+                update_action_text,
+            )
+                .distributive_run_if(is_showing_board),
+        )
+        .add_systems((create_pause, hide_board).in_schedule(OnEnter(AppState::Paused)))
+        .add_systems(
+            (despawn_screen::<PauseScreen>, show_board).in_schedule(OnExit(AppState::Paused)),
+        )
+        .add_system(create_game_over.in_schedule(OnEnter(AppState::GameOver)))
+        .add_system(despawn_screen::<GameOverScreen>.in_schedule(OnExit(AppState::GameOver)))
+        .add_system(create_results.in_schedule(OnEnter(AppState::Results)))
+        .add_system(despawn_screen::<ResultsScreen>.in_schedule(OnExit(AppState::Results)))
+        // リプレイは毎回ボードを作り直す
+        .add_systems(
+            (
+                despawn_screen::<GameScreen>,
+                create_game_screen,
+                create_replay_hud,
+            )
+                .chain()
+                .in_schedule(OnEnter(AppState::Replay)),
+        )
+        .add_system(update_replay_hud.in_set(OnUpdate(AppState::Replay)))
+        .add_system(despawn_screen::<ReplayScreen>.in_schedule(OnExit(AppState::Replay)))
+        // 再戦でも毎回ボードを作り直す ポーズからの再開では作り直さない
+        .add_systems(
+            (
+                despawn_screen::<GameScreen>,
+                create_versus_screen,
+                create_versus_countdown,
+            )
+                .chain()
+                .distributive_run_if(is_new_versus)
+                .in_schedule(OnEnter(AppState::Versus)),
+        )
+        .add_system(update_countdown.in_set(OnUpdate(AppState::Versus)))
+        .add_system(update_versus_countdown.in_set(OnUpdate(AppState::Versus)))
+        .add_system(despawn_screen::<CountdownScreen>.in_schedule(OnExit(AppState::Versus)))
+        .add_systems((create_versus_pause, hide_board).in_schedule(OnEnter(AppState::VersusPaused)))
+        .add_systems(
+            (despawn_screen::<PauseScreen>, show_board).in_schedule(OnExit(AppState::VersusPaused)),
+        )
+        .add_system(
+            create_versus_countdown
+                .run_if(is_counting_down)
+                .in_schedule(OnExit(AppState::VersusPaused)),
+        )
+        .add_system(create_versus_results.in_schedule(OnEnter(AppState::VersusResults)))
+        .add_system(
+            despawn_screen::<VersusResultsScreen>.in_schedule(OnExit(AppState::VersusResults)),
+        )
+        .add_system(render_dot_text);
     }
 }
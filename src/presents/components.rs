@@ -1,4 +1,76 @@
-use bevy::prelude::Component;
+use bevy::prelude::{Color, Component};
+
+use crate::entities::config::MenuItem;
+
+// どのプレイヤーの画面の部品か (1 人用では 0)
+#[derive(Component, Clone, Copy)]
+pub struct Player(pub usize);
 
 #[derive(Component)]
 pub struct BoardUnit(pub (usize, usize));
+
+#[derive(Component)]
+pub struct StockUnit(pub (usize, usize));
+
+// 何個目のネクストの、どのマスか
+#[derive(Component)]
+pub struct NextUnit(pub usize, pub (usize, usize));
+
+// ドット文字で描く文字列 左上が原点
+#[derive(Component)]
+pub struct DotText {
+    pub text: String,
+    pub color: Color,
+    pub unit: f32,
+}
+
+impl DotText {
+    pub fn new(text: &str) -> Self {
+        return DotText {
+            text: text.to_string(),
+            color: Color::WHITE,
+            unit: 3.,
+        };
+    }
+}
+
+// HUD の何行目か 何を表示するかはモードで決まる
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum HudField {
+    Label(usize),
+    Value(usize),
+    Action,
+}
+
+// 受けたせり上がりの段数を表示するバー
+#[derive(Component)]
+pub struct GarbageMeter;
+
+// 画面ごとの目印 その画面を抜けるときにまとめて消す
+#[derive(Component)]
+pub struct GameScreen;
+
+#[derive(Component)]
+pub struct MenuScreen;
+
+#[derive(Component)]
+pub struct CountdownScreen;
+
+#[derive(Component)]
+pub struct PauseScreen;
+
+#[derive(Component)]
+pub struct GameOverScreen;
+
+#[derive(Component)]
+pub struct ResultsScreen;
+
+#[derive(Component)]
+pub struct ReplayScreen;
+
+#[derive(Component)]
+pub struct VersusResultsScreen;
+
+// メニューの 1 行
+#[derive(Component)]
+pub struct MenuField(pub MenuItem);
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+
+use super::components::DotText;
+
+pub const GLYPH_WIDTH: usize = 3;
+pub const GLYPH_HEIGHT: usize = 5;
+
+// 3x5 のドット文字 上の段から、左のドットが上位ビット
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    return match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+        _ => [0; GLYPH_HEIGHT],
+    };
+}
+
+pub fn text_width(text: &str, unit: f32) -> f32 {
+    let count = text.chars().count();
+    if count == 0 {
+        return 0.;
+    }
+    return ((count * (GLYPH_WIDTH + 1) - 1) as f32) * unit;
+}
+
+// center を中心にした左上の位置
+pub fn centered(text: &str, unit: f32, center: Vec3) -> Transform {
+    let height = GLYPH_HEIGHT as f32 * unit;
+    return Transform::from_translation(
+        center + Vec3::new(-text_width(text, unit) / 2., height / 2., 0.),
+    );
+}
+
+// 文字が変わったらドットを作り直す
+pub fn render_dot_text(mut commands: Commands, query: Query<(Entity, &DotText), Changed<DotText>>) {
+    for (entity, text) in query.iter() {
+        commands.entity(entity).despawn_descendants();
+        commands.entity(entity).with_children(|parent| {
+            for (i, c) in text.text.chars().enumerate() {
+                for (row, bits) in glyph(c).iter().enumerate() {
+                    for col in 0..GLYPH_WIDTH {
+                        if bits & (0b100 >> col) == 0 {
+                            continue;
+                        }
+
+                        let _x = (i * (GLYPH_WIDTH + 1) + col) as f32;
+                        let _y = -(row as f32);
+                        parent.spawn(SpriteBundle {
+                            sprite: Sprite {
+                                color: text.color,
+                                custom_size: Some(Vec2::new(1., 1.) * text.unit),
+                                ..Default::default()
+                            },
+                            transform: Transform {
+                                translation: Vec3::new(_x * text.unit, _y * text.unit, 2.),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+        });
+    }
+}
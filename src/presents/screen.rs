@@ -0,0 +1,466 @@
+use bevy::prelude::*;
+
+use tetris_core::{
+    config::FRAMES_PER_SECOND,
+    counter::{format_frame_diff, format_frames, format_frames_millis},
+    event::GameOverEvent,
+    game::Game,
+    mode::{GameEnd, GameMode, SPLIT_LINES},
+    versus::VersusResult,
+};
+
+use crate::entities::{
+    config::{AppSettings, MenuCursor, MenuItem, COUNTDOWN_FRAMES},
+    counter::StateTimer,
+    game::{CurrentGame, CurrentVersus, PersonalBest, ReplayPlayback},
+};
+
+use super::components::{
+    BoardUnit, CountdownScreen, DotText, GameOverScreen, MenuField, MenuScreen, NextUnit,
+    PauseScreen, ReplayScreen, ResultsScreen, StockUnit, VersusResultsScreen,
+};
+use super::font::centered;
+use super::system::{diff_color, VERSUS_ORIGINS};
+
+// ボードの見えている部分の中心
+const BOARD_CENTER: Vec3 = Vec3::new(-10., 10., 3.);
+const BACKDROP_SIZE: Vec2 = Vec2::new(300., 520.);
+const TITLE_UNIT: f32 = 6.;
+const LINE_HEIGHT: f32 = 28.;
+// ボードより上の余白にリプレイの状態や対戦の操作を表示する
+const REPLAY_HUD_TOP: f32 = 318.;
+const REPLAY_HUD_UNIT: f32 = 2.;
+
+pub fn despawn_screen<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn create_menu(mut commands: Commands, settings: Res<AppSettings>, cursor: Res<MenuCursor>) {
+    spawn_text(&mut commands, "TETRIS", TITLE_UNIT, 0., 120., MenuScreen);
+
+    for item in MenuItem::ALL {
+        let text = menu_text(&settings, item);
+        commands.spawn((
+            SpatialBundle::from_transform(centered(&text, 3., menu_center(item))),
+            DotText {
+                color: menu_color(&cursor, item),
+                ..DotText::new(&text)
+            },
+            MenuField(item),
+            MenuScreen,
+        ));
+    }
+
+    let bottom = -(MenuItem::ALL.len() as f32 + 1.) * LINE_HEIGHT;
+    spawn_text(&mut commands, "ENTER: START", 3., 0., bottom, MenuScreen);
+}
+
+pub fn update_menu(
+    settings: Res<AppSettings>,
+    cursor: Res<MenuCursor>,
+    mut query: Query<(&MenuField, &mut DotText, &mut Transform)>,
+) {
+    if !settings.is_changed() && !cursor.is_changed() {
+        return;
+    }
+
+    for (field, mut text, mut transform) in query.iter_mut() {
+        text.text = menu_text(&settings, field.0);
+        text.color = menu_color(&cursor, field.0);
+        *transform = centered(&text.text, text.unit, menu_center(field.0));
+    }
+}
+
+fn menu_center(item: MenuItem) -> Vec3 {
+    let row = MenuItem::ALL
+        .iter()
+        .position(|other| *other == item)
+        .unwrap_or(0);
+    return Vec3::new(0., -(row as f32) * LINE_HEIGHT, 3.);
+}
+
+fn menu_text(settings: &AppSettings, item: MenuItem) -> String {
+    return match item {
+        MenuItem::Players => match (settings.is_versus, settings.cpu) {
+            (false, _) => String::from("PLAYERS < 1P >"),
+            (true, None) => String::from("PLAYERS < 2P >"),
+            (true, Some(cpu)) => format!("PLAYERS < CPU {} >", cpu.name()),
+        },
+        MenuItem::Mode => format!("MODE < {} >", settings.game.mode.name()),
+        MenuItem::Rotation => format!("ROTATION < {} >", settings.game.rotation_system.name()),
+        MenuItem::Randomizer => format!("RANDOMIZER < {} >", settings.game.randomizer.name()),
+        MenuItem::Seed => match settings.game.seed {
+            Some(seed) => format!("SEED {}", seed),
+            None => String::from("SEED RANDOM"),
+        },
+    };
+}
+
+// 選んでいる行だけ明るくする
+fn menu_color(cursor: &MenuCursor, item: MenuItem) -> Color {
+    if cursor.item() == item {
+        return Color::WHITE;
+    }
+    return Color::GRAY;
+}
+
+// タイマーは同じ OnEnter で入れるのでまだ読めない
+pub fn create_countdown(mut commands: Commands) {
+    spawn_countdown(&mut commands, BOARD_CENTER);
+}
+
+pub fn create_versus_countdown(mut commands: Commands) {
+    for origin in VERSUS_ORIGINS {
+        spawn_countdown(&mut commands, origin + BOARD_CENTER);
+    }
+}
+
+fn spawn_countdown(commands: &mut Commands, center: Vec3) {
+    let text = countdown_text(&StateTimer::new(COUNTDOWN_FRAMES));
+    commands.spawn((
+        SpatialBundle::from_transform(centered(&text, TITLE_UNIT, center)),
+        DotText::new(&text),
+        CountdownScreen,
+    ));
+}
+
+pub fn update_countdown(
+    timer: Res<StateTimer>,
+    mut query: Query<&mut DotText, With<CountdownScreen>>,
+) {
+    let text = countdown_text(&timer);
+    for mut dot_text in query.iter_mut() {
+        if dot_text.text != text {
+            dot_text.text = text.clone();
+        }
+    }
+}
+
+// 対戦はカウントダウンも同じ状態の中で進めるので、終わったら消す
+pub fn update_versus_countdown(
+    mut commands: Commands,
+    timer: Res<StateTimer>,
+    query: Query<Entity, With<CountdownScreen>>,
+) {
+    if !timer.is_finished() {
+        return;
+    }
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn countdown_text(timer: &StateTimer) -> String {
+    let seconds = (timer.remaining + FRAMES_PER_SECOND - 1) / FRAMES_PER_SECOND;
+    return seconds.max(1).to_string();
+}
+
+pub fn create_pause(mut commands: Commands) {
+    spawn_backdrop(&mut commands, BOARD_CENTER, PauseScreen);
+
+    let lines = ["PAUSE", "", "ESC: RESUME", "Q: MENU"];
+    let top = BOARD_CENTER.y + LINE_HEIGHT;
+    for (i, line) in lines.iter().enumerate() {
+        let _y = top - i as f32 * LINE_HEIGHT;
+        spawn_text(&mut commands, line, 3., BOARD_CENTER.x, _y, PauseScreen);
+    }
+}
+
+// 対戦では両方のボードを隠す 操作の説明はボードの上の余白に出す
+pub fn create_versus_pause(mut commands: Commands) {
+    for origin in VERSUS_ORIGINS {
+        let center = origin + BOARD_CENTER;
+        spawn_backdrop(&mut commands, center, PauseScreen);
+        spawn_text(&mut commands, "PAUSE", 3., center.x, center.y, PauseScreen);
+    }
+    spawn_text(
+        &mut commands,
+        "ESC: RESUME  Q: MENU",
+        REPLAY_HUD_UNIT,
+        0.,
+        REPLAY_HUD_TOP,
+        PauseScreen,
+    );
+}
+
+// ポーズ中に盤面とミノを盗み見できないようにする
+pub fn hide_board(mut query: Query<&mut Visibility, BoardContents>) {
+    for mut visibility in query.iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+pub fn show_board(mut query: Query<&mut Visibility, BoardContents>) {
+    for mut visibility in query.iter_mut() {
+        *visibility = Visibility::Inherited;
+    }
+}
+
+type BoardContents = Or<(With<BoardUnit>, With<StockUnit>, With<NextUnit>)>;
+
+pub fn create_game_over(
+    mut commands: Commands,
+    mut game_over_r: EventReader<GameOverEvent>,
+    game: Res<CurrentGame>,
+) {
+    spawn_backdrop(&mut commands, BOARD_CENTER, GameOverScreen);
+
+    // 目標を達成したときは理由の代わりにタイムか得点を出す
+    let cause = game_over_r.iter().last().map(|event| event.cause);
+    let (title, detail) = match cause {
+        Some(GameEnd::TimeUp) => (
+            GameEnd::TimeUp.name().to_string(),
+            format!("SCORE {}", game.score.score),
+        ),
+        Some(cause) if cause.is_cleared() => (
+            cause.name().to_string(),
+            format_frames_millis(game.clock.frames),
+        ),
+        Some(cause) => (String::from("GAME OVER"), cause.name().to_string()),
+        None => (String::from("GAME OVER"), String::new()),
+    };
+    spawn_text(
+        &mut commands,
+        &title,
+        4.,
+        BOARD_CENTER.x,
+        BOARD_CENTER.y,
+        GameOverScreen,
+    );
+    spawn_text(
+        &mut commands,
+        &detail,
+        3.,
+        BOARD_CENTER.x,
+        BOARD_CENTER.y - LINE_HEIGHT * 1.5,
+        GameOverScreen,
+    );
+}
+
+pub fn create_results(mut commands: Commands, game: Res<CurrentGame>, best: Res<PersonalBest>) {
+    spawn_backdrop(&mut commands, BOARD_CENTER, ResultsScreen);
+    if let GameMode::Sprint { .. } = game.settings.mode {
+        create_sprint_results(&mut commands, &game, &best);
+        return;
+    }
+
+    let score = &game.score;
+
+    let lines = [
+        String::from("RESULTS"),
+        game.settings.mode.name(),
+        String::new(),
+        format!("SCORE {}", score.score),
+        format!("LINES {}", score.lines),
+        format!("LEVEL {}", score.level),
+        format!("PIECES {}", score.pieces),
+        format!("TIME {}", game.clock.format()),
+        format!("SEED {}", game.seed),
+        String::new(),
+        String::from("ENTER: RETRY"),
+        String::from("V: REPLAY"),
+        String::from("ESC: MENU"),
+    ];
+
+    let top = BOARD_CENTER.y + LINE_HEIGHT * 4.;
+    for (i, line) in lines.iter().enumerate() {
+        let _y = top - i as f32 * LINE_HEIGHT;
+        spawn_text(&mut commands, line, 3., BOARD_CENTER.x, _y, ResultsScreen);
+    }
+}
+
+// タイムと 10 ラインごとのスプリットを自己ベストと並べる
+fn create_sprint_results(commands: &mut Commands, game: &Game, best: &PersonalBest) {
+    let record = best.record.as_ref();
+    let mut lines = vec![
+        (game.settings.mode.name(), Color::WHITE),
+        (String::new(), Color::WHITE),
+    ];
+
+    let is_cleared = game.end.is_some_and(|end| end.is_cleared());
+    if is_cleared {
+        lines.push((
+            format!("TIME {}", format_frames_millis(game.clock.frames)),
+            Color::WHITE,
+        ));
+    } else {
+        lines.push((String::from("NOT FINISHED"), Color::GRAY));
+    }
+    if best.is_updated {
+        lines.push((String::from("NEW BEST"), Color::GREEN));
+    } else if let Some(record) = record {
+        lines.push((
+            format!("BEST {}", format_frames_millis(record.frames)),
+            Color::GRAY,
+        ));
+    }
+    lines.push((String::new(), Color::WHITE));
+
+    for (i, frames) in game.splits.iter().enumerate() {
+        let mut text = format!(
+            "{:>3} {}",
+            (i as i64 + 1) * SPLIT_LINES,
+            format_frames_millis(*frames)
+        );
+        let mut color = Color::WHITE;
+        if let Some(diff) = record.and_then(|record| record.split_diff(i, *frames)) {
+            text.push(' ');
+            text.push_str(&format_frame_diff(diff));
+            color = diff_color(diff);
+        }
+        lines.push((text, color));
+    }
+    lines.push((String::new(), Color::WHITE));
+    for text in ["ENTER: RETRY", "V: REPLAY", "ESC: MENU"] {
+        lines.push((String::from(text), Color::WHITE));
+    }
+
+    // 100 ラインでも収まるようにスプリントは小さめに詰める
+    let line_height = LINE_HEIGHT * 0.75;
+    let top = BOARD_CENTER.y + line_height * (lines.len() as f32 - 1.) / 2.;
+    for (i, (text, color)) in lines.iter().enumerate() {
+        let _y = top - i as f32 * line_height;
+        commands.spawn((
+            SpatialBundle::from_transform(centered(
+                text,
+                2.,
+                Vec3::new(BOARD_CENTER.x, _y, BOARD_CENTER.z),
+            )),
+            DotText {
+                color: *color,
+                unit: 2.,
+                ..DotText::new(text)
+            },
+            ResultsScreen,
+        ));
+    }
+}
+
+// それぞれのボードの上に勝ち負けを出す
+pub fn create_versus_results(mut commands: Commands, versus: Res<CurrentVersus>) {
+    for (player, origin) in VERSUS_ORIGINS.iter().enumerate() {
+        let center = *origin + BOARD_CENTER;
+        spawn_backdrop(&mut commands, center, VersusResultsScreen);
+
+        let (title, color) = match versus.result {
+            Some(VersusResult::Win(winner)) if winner == player => ("WIN", Color::GREEN),
+            Some(VersusResult::Win(_)) => ("LOSE", Color::RED),
+            _ => ("DRAW", Color::WHITE),
+        };
+        commands.spawn((
+            SpatialBundle::from_transform(centered(title, TITLE_UNIT, center)),
+            DotText {
+                color: color,
+                unit: TITLE_UNIT,
+                ..DotText::new(title)
+            },
+            VersusResultsScreen,
+        ));
+
+        let game = &versus.players[player];
+        let detail = match game.end {
+            Some(end) => end.name().to_string(),
+            None => format!("LINES {}", game.score.lines),
+        };
+        spawn_text(
+            &mut commands,
+            &detail,
+            3.,
+            center.x,
+            center.y - LINE_HEIGHT * 1.5,
+            VersusResultsScreen,
+        );
+    }
+
+    // ボードの間は相手のネクストで埋まっているので上の余白に出す
+    spawn_text(
+        &mut commands,
+        "ENTER: REMATCH  ESC: MENU",
+        REPLAY_HUD_UNIT,
+        0.,
+        REPLAY_HUD_TOP,
+        VersusResultsScreen,
+    );
+}
+
+pub fn create_replay_hud(mut commands: Commands, playback: Res<ReplayPlayback>) {
+    let lines = [
+        replay_status_text(&playback),
+        String::from("SPACE: PAUSE UP/DOWN: SPEED LEFT/RIGHT: SEEK ESC: MENU"),
+    ];
+    for (i, line) in lines.iter().enumerate() {
+        let _y = REPLAY_HUD_TOP - i as f32 * LINE_HEIGHT * 0.6;
+        spawn_text(&mut commands, line, REPLAY_HUD_UNIT, 0., _y, ReplayScreen);
+    }
+}
+
+pub fn update_replay_hud(
+    playback: Res<ReplayPlayback>,
+    mut query: Query<(&mut DotText, &mut Transform), With<ReplayScreen>>,
+) {
+    if !playback.is_changed() {
+        return;
+    }
+
+    // 1 行目だけが変わる
+    let text = replay_status_text(&playback);
+    let center = Vec3::new(0., REPLAY_HUD_TOP, BOARD_CENTER.z);
+    for (mut dot_text, mut transform) in query.iter_mut() {
+        if dot_text.text.starts_with("REPLAY") && dot_text.text != text {
+            dot_text.text = text.clone();
+            *transform = centered(&text, REPLAY_HUD_UNIT, center);
+        }
+    }
+}
+
+fn replay_status_text(playback: &ReplayPlayback) -> String {
+    let player = &playback.player;
+    let mut text = format!(
+        "REPLAY {}X {} / {}",
+        playback.speed(),
+        format_frames(player.frame as i64),
+        format_frames(player.replay.frames as i64)
+    );
+    if playback.is_paused {
+        text.push_str(" PAUSED");
+    } else if player.is_finished() {
+        text.push_str(" END");
+    }
+    return text;
+}
+
+// ボードの上を暗くする
+fn spawn_backdrop(commands: &mut Commands, center: Vec3, marker: impl Component) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0., 0., 0., 0.85),
+                custom_size: Some(BACKDROP_SIZE),
+                ..Default::default()
+            },
+            transform: Transform::from_translation(center - Vec3::new(0., 0., 1.)),
+            ..Default::default()
+        },
+        marker,
+    ));
+}
+
+fn spawn_text(
+    commands: &mut Commands,
+    text: &str,
+    unit: f32,
+    x: f32,
+    y: f32,
+    marker: impl Component,
+) {
+    commands.spawn((
+        SpatialBundle::from_transform(centered(text, unit, Vec3::new(x, y, BOARD_CENTER.z))),
+        DotText {
+            unit: unit,
+            ..DotText::new(text)
+        },
+        marker,
+    ));
+}
@@ -1,13 +1,94 @@
 use bevy::prelude::*;
 
-use crate::entities::{event::BoardUpdateEvent, tetris::GameBoard};
+use bevy::sprite::Anchor;
+use tetris_core::{
+    config::{HEIGHT_WITH_PADDING, TETRIMONO_MATRIX_SIZE, VISIBLE_HEIGHT, WIDTH_WITH_PADDING},
+    counter::{format_frame_diff, format_frames, format_frames_millis},
+    event::ScoreKind,
+    game::Game,
+    mode::{HudItem, SPLIT_LINES},
+    record::SprintRecord,
+    score::SpinType,
+    tetrimono::Tetromino,
+    tetris::GameBoard,
+    versus::PLAYER_COUNT,
+};
 
-use super::components::BoardUnit;
+use crate::entities::{
+    config::AppSettings,
+    event::{BoardUpdateEvent, PlayerScoreEvent},
+    game::{PersonalBest, Players},
+};
+
+use super::components::{
+    BoardUnit, DotText, GameScreen, GarbageMeter, HudField, NextUnit, Player, StockUnit,
+};
+use super::font::centered;
 
 const BOARD_UNIT_SIZE: f32 = 20.;
+const GHOST_ALPHA: f32 = 0.3;
 const BOARD_BOTTOM_LEFT: Vec3 = Vec3::new(-7. * BOARD_UNIT_SIZE, -12. * BOARD_UNIT_SIZE, 0.);
+// ボードの左上あたりにホールド中のミノを表示する
+const STOCK_BOTTOM_LEFT: Vec3 = Vec3::new(-11.5 * BOARD_UNIT_SIZE, 6. * BOARD_UNIT_SIZE, 0.);
+// ボードの右にネクストを上から並べる
+const NEXT_TOP_LEFT: Vec3 = Vec3::new(8. * BOARD_UNIT_SIZE, 13. * BOARD_UNIT_SIZE, 0.);
+const NEXT_UNIT_SIZE: f32 = 16.;
+// ホールドの下に得点などを表示する
+const HUD_TOP_LEFT: Vec3 = Vec3::new(-12. * BOARD_UNIT_SIZE, 4. * BOARD_UNIT_SIZE, 0.);
+const HUD_LINE_HEIGHT: f32 = 24.;
+// モードが使う HUD の行数の上限
+const HUD_SLOTS: usize = 4;
+// これより長い値はボードの壁に重ならないように小さく描く
+const HUD_MAX_CHARS: usize = 7;
+// ボードの下に直前の消し方を表示する
+const ACTION_TOP_LEFT: Vec3 = Vec3::new(-7. * BOARD_UNIT_SIZE, -13. * BOARD_UNIT_SIZE, 0.);
+// 対戦で左右のプレイヤーの画面をずらす幅 (1 人用の画面 1 つ分)
+pub const VERSUS_ORIGINS: [Vec3; PLAYER_COUNT] =
+    [Vec3::new(-250., 0., 0.), Vec3::new(250., 0., 0.)];
+// ボードの上に 1P / 2P を表示する
+const PLAYER_LABEL_CENTER: Vec3 = Vec3::new(-10., 13.5 * BOARD_UNIT_SIZE, 0.);
+// 左の壁に重ねて、せり上がりの床から伸ばす
+const GARBAGE_METER_BOTTOM: Vec3 = Vec3::new(-7. * BOARD_UNIT_SIZE, -10.5 * BOARD_UNIT_SIZE, 2.);
+const GARBAGE_METER_WIDTH: f32 = BOARD_UNIT_SIZE / 2.;
+
+// 1 人用は画面の真ん中に 1 人分だけ並べる
+pub fn create_game_screen(mut commands: Commands, settings: Res<AppSettings>) {
+    spawn_player_screen(&mut commands, &settings, 0, Vec3::ZERO);
+}
+
+// 2 人対戦は左右に 1 人分ずつ並べて、ボードの横にせり上がりのメーターを置く
+pub fn create_versus_screen(mut commands: Commands, settings: Res<AppSettings>) {
+    for (player, origin) in VERSUS_ORIGINS.iter().enumerate() {
+        spawn_player_screen(&mut commands, &settings, player, *origin);
+        spawn_garbage_meter(&mut commands, player, *origin);
+
+        let label = match settings.cpu {
+            Some(_) if player == 1 => String::from("CPU"),
+            _ => format!("{}P", player + 1),
+        };
+        commands.spawn((
+            SpatialBundle::from_transform(centered(&label, 3., *origin + PLAYER_LABEL_CENTER)),
+            DotText::new(&label),
+            GameScreen,
+        ));
+    }
+}
 
-pub fn create_board(mut commands: Commands, board: Res<GameBoard>) {
+fn spawn_player_screen(
+    commands: &mut Commands,
+    settings: &AppSettings,
+    player: usize,
+    origin: Vec3,
+) {
+    spawn_board(commands, player, origin);
+    spawn_stock(commands, player, origin);
+    spawn_next(commands, settings.next_count, player, origin);
+    spawn_hud(commands, player, origin);
+}
+
+// 壁と床だけのボードを作る 中身は update_board で塗る
+fn spawn_board(commands: &mut Commands, player: usize, origin: Vec3) {
+    let board = GameBoard::default();
     for (i, row) in board.board.iter().enumerate() {
         for (j, col) in row.iter().enumerate() {
             let _y = i as f32;
@@ -20,36 +101,426 @@ pub fn create_board(mut commands: Commands, board: Res<GameBoard>) {
                         ..Default::default()
                     },
                     transform: Transform {
-                        translation: BOARD_BOTTOM_LEFT
+                        translation: origin
+                            + BOARD_BOTTOM_LEFT
                             + Vec3::new(_x * BOARD_UNIT_SIZE, _y * BOARD_UNIT_SIZE, 1.),
                         ..Default::default()
                     },
                     ..Default::default()
                 })
-                .insert(BoardUnit((j, i)));
+                .insert((BoardUnit((j, i)), Player(player), GameScreen));
         }
     }
 }
 
 pub fn update_board(
     mut event_r: EventReader<BoardUpdateEvent>,
-    board: Res<GameBoard>,
-    mut query: Query<(&mut BoardUnit, &mut Sprite)>,
+    players: Players,
+    settings: Res<AppSettings>,
+    mut query: Query<(&Player, &BoardUnit, &mut Sprite)>,
 ) {
     if event_r.is_empty() {
         return;
     }
 
-    let clone = board.get_written_clone();
-    for (unit, mut sprite) in query.iter_mut() {
+    let mut boards = Vec::new();
+    for player in 0..PLAYER_COUNT {
+        let board = match players.get(player) {
+            Some(game) => &game.board,
+            None => break,
+        };
+        let ghost = if settings.show_ghost {
+            get_ghost(board)
+        } else {
+            [[0; WIDTH_WITH_PADDING]; HEIGHT_WITH_PADDING]
+        };
+        boards.push((board.get_written_clone(), ghost));
+    }
+
+    for (player, unit, mut sprite) in query.iter_mut() {
+        let (clone, ghost) = match boards.get(player.0) {
+            Some(board) => board,
+            None => continue,
+        };
         let cell = clone[unit.0 .1][unit.0 .0];
-        sprite.color = match_color(cell);
+        let ghost_cell = ghost[unit.0 .1][unit.0 .0];
+        sprite.color = if cell == 0 && ghost_cell != 0 {
+            // ゴーストは薄く表示する
+            match_color(ghost_cell).with_a(GHOST_ALPHA)
+        } else {
+            match_color(cell)
+        };
     }
 
     event_r.clear();
 }
 
-fn match_color(cell: u8) -> Color {
+fn get_ghost(board: &GameBoard) -> [[u8; WIDTH_WITH_PADDING]; HEIGHT_WITH_PADDING] {
+    let mut ghost = [[0; WIDTH_WITH_PADDING]; HEIGHT_WITH_PADDING];
+    let (tetrimono, cursor) = match (board.tetrimono, board.get_ghost_cursor()) {
+        (Some(tetrimono), Some(cursor)) => (tetrimono, cursor),
+        _ => return ghost,
+    };
+
+    for y in 0..4 {
+        for x in 0..4 {
+            if tetrimono.figure[y][x] == 0 {
+                continue;
+            }
+            ghost[cursor.0 + y][cursor.1 + x] = tetrimono.figure[y][x];
+        }
+    }
+    return ghost;
+}
+
+fn spawn_stock(commands: &mut Commands, player: usize, origin: Vec3) {
+    for i in 0..TETRIMONO_MATRIX_SIZE {
+        for j in 0..TETRIMONO_MATRIX_SIZE {
+            let _y = i as f32;
+            let _x = j as f32;
+            commands
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::BLACK,
+                        custom_size: Some(Vec2::new(1., 1.) * BOARD_UNIT_SIZE),
+                        ..Default::default()
+                    },
+                    transform: Transform {
+                        translation: origin
+                            + STOCK_BOTTOM_LEFT
+                            + Vec3::new(_x * BOARD_UNIT_SIZE, _y * BOARD_UNIT_SIZE, 1.),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert((StockUnit((j, i)), Player(player), GameScreen));
+        }
+    }
+}
+
+pub fn update_stock(players: Players, mut query: Query<(&Player, &StockUnit, &mut Sprite)>) {
+    if !players.is_changed() {
+        return;
+    }
+
+    for (player, unit, mut sprite) in query.iter_mut() {
+        let stock = match players.get(player.0) {
+            Some(game) => &game.stock,
+            None => continue,
+        };
+        let cell = match stock.mono {
+            Some(mono) => mono.figure[unit.0 .1][unit.0 .0],
+            None => 0,
+        };
+
+        sprite.color = if cell == 0 {
+            Color::BLACK
+        } else if stock.is_used {
+            // 使用済みの間は暗く表示する
+            Color::DARK_GRAY
+        } else {
+            match_color(cell)
+        };
+    }
+}
+
+fn spawn_next(commands: &mut Commands, next_count: usize, player: usize, origin: Vec3) {
+    for slot in 0..next_count {
+        let top = -((slot * TETRIMONO_MATRIX_SIZE) as f32) * NEXT_UNIT_SIZE;
+        for i in 0..TETRIMONO_MATRIX_SIZE {
+            for j in 0..TETRIMONO_MATRIX_SIZE {
+                let _y = i as f32;
+                let _x = j as f32;
+                commands
+                    .spawn(SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::BLACK,
+                            custom_size: Some(Vec2::new(1., 1.) * NEXT_UNIT_SIZE),
+                            ..Default::default()
+                        },
+                        transform: Transform {
+                            translation: origin
+                                + NEXT_TOP_LEFT
+                                + Vec3::new(
+                                    _x * NEXT_UNIT_SIZE,
+                                    top - (TETRIMONO_MATRIX_SIZE as f32 - _y) * NEXT_UNIT_SIZE,
+                                    1.,
+                                ),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .insert((NextUnit(slot, (j, i)), Player(player), GameScreen));
+            }
+        }
+    }
+}
+
+pub fn update_next(players: Players, mut query: Query<(&Player, &NextUnit, &mut Sprite)>) {
+    if !players.is_changed() {
+        return;
+    }
+
+    for (player, unit, mut sprite) in query.iter_mut() {
+        let game = match players.get(player.0) {
+            Some(game) => game,
+            None => continue,
+        };
+        let system = game.board.rotation_system.get();
+        let cell = match game.queue.queue.get(unit.0) {
+            Some(typ) => Tetromino::spawn(*typ, system).figure[unit.1 .1][unit.1 .0],
+            None => 0,
+        };
+
+        sprite.color = if cell == 0 {
+            Color::BLACK
+        } else {
+            match_color(cell)
+        };
+    }
+}
+
+// 中身は update_hud でモードに合わせて書く
+fn spawn_hud(commands: &mut Commands, player: usize, origin: Vec3) {
+    for i in 0..HUD_SLOTS {
+        let _y = -((i * 2) as f32) * HUD_LINE_HEIGHT;
+        commands.spawn((
+            SpatialBundle::from_transform(Transform::from_translation(
+                origin + HUD_TOP_LEFT + Vec3::new(0., _y, 0.),
+            )),
+            DotText {
+                color: Color::GRAY,
+                ..DotText::new("")
+            },
+            HudField::Label(i),
+            Player(player),
+            GameScreen,
+        ));
+        commands.spawn((
+            SpatialBundle::from_transform(Transform::from_translation(
+                origin + HUD_TOP_LEFT + Vec3::new(0., _y - HUD_LINE_HEIGHT, 0.),
+            )),
+            DotText::new(""),
+            HudField::Value(i),
+            Player(player),
+            GameScreen,
+        ));
+    }
+
+    commands.spawn((
+        SpatialBundle::from_transform(Transform::from_translation(origin + ACTION_TOP_LEFT)),
+        DotText {
+            unit: 2.,
+            ..DotText::new("")
+        },
+        HudField::Action,
+        Player(player),
+        GameScreen,
+    ));
+}
+
+// 床から上に向かって伸びる 中身は update_garbage_meter で決める
+fn spawn_garbage_meter(commands: &mut Commands, player: usize, origin: Vec3) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::RED,
+                custom_size: Some(Vec2::new(GARBAGE_METER_WIDTH, 0.)),
+                anchor: Anchor::BottomCenter,
+                ..Default::default()
+            },
+            transform: Transform::from_translation(origin + GARBAGE_METER_BOTTOM),
+            ..Default::default()
+        },
+        GarbageMeter,
+        Player(player),
+        GameScreen,
+    ));
+}
+
+// 受けて、まだせり上がっていない段数だけ伸ばす 待ち時間の間は橙色にする
+pub fn update_garbage_meter(
+    players: Players,
+    mut query: Query<(&Player, &mut Sprite), With<GarbageMeter>>,
+) {
+    if !players.is_changed() {
+        return;
+    }
+
+    for (player, mut sprite) in query.iter_mut() {
+        let (lines, is_ready) = match players.get(player.0) {
+            Some(game) => (game.pending_garbage_lines(), game.ready_garbage_lines() > 0),
+            None => (0, false),
+        };
+        let height = lines.min(VISIBLE_HEIGHT as i64) as f32 * BOARD_UNIT_SIZE;
+        let size = Vec2::new(GARBAGE_METER_WIDTH, height);
+        let color = if is_ready { Color::RED } else { Color::ORANGE };
+        if sprite.custom_size != Some(size) || sprite.color != color {
+            sprite.custom_size = Some(size);
+            sprite.color = color;
+        }
+    }
+}
+
+pub fn update_hud(
+    players: Players,
+    best: Option<Res<PersonalBest>>,
+    mut query: Query<(&Player, &HudField, &mut DotText)>,
+) {
+    if !players.is_changed() {
+        return;
+    }
+
+    // 対戦では自己ベストと比べない
+    let record = best
+        .as_ref()
+        .filter(|_| !players.is_versus())
+        .and_then(|best| best.record.as_ref());
+    for (player, field, mut text) in query.iter_mut() {
+        let game = match players.get(player.0) {
+            Some(game) => game,
+            None => continue,
+        };
+        let items = game.settings.mode.hud_items();
+        match *field {
+            HudField::Label(i) => {
+                let label = items
+                    .get(i)
+                    .map_or(String::new(), |item| hud_label(game, *item));
+                set_text(&mut text, &label);
+            }
+            HudField::Value(i) => {
+                let (value, color) = items.get(i).map_or((String::new(), Color::WHITE), |item| {
+                    hud_value(game, record, *item)
+                });
+                let unit = if value.len() > HUD_MAX_CHARS { 2. } else { 3. };
+                set_text(&mut text, &value);
+                if text.color != color || text.unit != unit {
+                    text.color = color;
+                    text.unit = unit;
+                }
+            }
+            HudField::Action => {}
+        }
+    }
+}
+
+fn hud_label(game: &Game, item: HudItem) -> String {
+    return match item {
+        HudItem::Score => String::from("SCORE"),
+        HudItem::Lines => String::from("LINES"),
+        HudItem::Level => String::from("LEVEL"),
+        HudItem::Time | HudItem::TimeLeft => String::from("TIME"),
+        HudItem::Garbage => String::from("GARBAGE"),
+        HudItem::Pieces => String::from("PIECES"),
+        HudItem::Split => match game.splits.len() {
+            0 => String::from("SPLIT"),
+            count => format!("SPLIT {}", count as i64 * SPLIT_LINES),
+        },
+    };
+}
+
+// 自己ベストより速ければ緑、遅ければ赤
+fn hud_value(game: &Game, record: Option<&SprintRecord>, item: HudItem) -> (String, Color) {
+    let score = &game.score;
+    let value = match item {
+        HudItem::Score => score.score.to_string(),
+        HudItem::Lines => match game.settings.mode.goal_lines() {
+            Some(goal) => format!("{}/{}", score.lines.min(goal), goal),
+            None => score.lines.to_string(),
+        },
+        HudItem::Level => score.level.to_string(),
+        HudItem::Time => format_frames_millis(game.clock.frames),
+        HudItem::Garbage => game.garbage_left().to_string(),
+        HudItem::Pieces => score.pieces.to_string(),
+        HudItem::TimeLeft => {
+            let limit = game.settings.mode.time_limit().unwrap_or(0);
+            format_frames((limit - game.clock.frames).max(0))
+        }
+        HudItem::Split => {
+            let index = match game.splits.len() {
+                0 => return (String::from("-"), Color::WHITE),
+                count => count - 1,
+            };
+            let frames = game.splits[index];
+            return match record.and_then(|record| record.split_diff(index, frames)) {
+                Some(diff) => (format_frame_diff(diff), diff_color(diff)),
+                None => (format_frames_millis(frames), Color::WHITE),
+            };
+        }
+    };
+    return (value, Color::WHITE);
+}
+
+pub fn diff_color(diff: i64) -> Color {
+    if diff < 0 {
+        return Color::GREEN;
+    }
+    if diff > 0 {
+        return Color::RED;
+    }
+    return Color::WHITE;
+}
+
+// 同じフレームに届いた得点をプレイヤーごとにまとめて 1 行にする
+pub fn update_action_text(
+    mut event_r: EventReader<PlayerScoreEvent>,
+    mut query: Query<(&Player, &HudField, &mut DotText)>,
+) {
+    let mut parts: [Vec<String>; PLAYER_COUNT] = Default::default();
+    let mut points = [0; PLAYER_COUNT];
+
+    for PlayerScoreEvent { player, event } in event_r.iter() {
+        let part = match event.kind {
+            ScoreKind::SoftDrop { .. } | ScoreKind::HardDrop { .. } => continue,
+            ScoreKind::LineClear {
+                lines,
+                spin,
+                back_to_back,
+            } => {
+                let mut words: Vec<&str> = Vec::new();
+                if back_to_back {
+                    words.push("B2B");
+                }
+                match spin {
+                    SpinType::None => {}
+                    SpinType::Mini => words.push("MINI T-SPIN"),
+                    SpinType::Full => words.push("T-SPIN"),
+                }
+                match lines {
+                    0 => {}
+                    1 => words.push("SINGLE"),
+                    2 => words.push("DOUBLE"),
+                    3 => words.push("TRIPLE"),
+                    _ => words.push("TETRIS"),
+                }
+                words.join(" ")
+            }
+            ScoreKind::Combo { count } => format!("COMBO {}", count),
+            ScoreKind::PerfectClear { .. } => String::from("PERFECT CLEAR"),
+        };
+        parts[*player].push(part);
+        points[*player] += event.points;
+    }
+
+    for (player, field, mut text) in query.iter_mut() {
+        let parts = &parts[player.0];
+        if *field != HudField::Action || parts.is_empty() {
+            continue;
+        }
+        let line = format!("{}  +{}", parts.join("  "), points[player.0]);
+        set_text(&mut text, &line);
+    }
+}
+
+// 変わったときだけ書き換えて描き直しを減らす
+fn set_text(text: &mut Mut<DotText>, value: &str) {
+    if text.text != value {
+        text.text = value.to_string();
+    }
+}
+
+pub fn match_color(cell: u8) -> Color {
     match cell {
         0 => Color::GRAY,
         1 => Color::PURPLE,
@@ -0,0 +1,581 @@
+use super::config::{FRAMES_PER_SECOND, HEIGHT_PADDING, MAX_HEIGHT, MAX_WIDTH};
+use super::event::LockEvent;
+use super::game::{Game, Inputs};
+use super::garbage::GarbageRules;
+use super::movegen::{find_placements, find_spawn_placements, InputPlayer, MoveInput, Placement};
+use super::random::Pcg32;
+use super::score::{Score, SpinType};
+use super::tetrimono::TetrominoType;
+use super::tetris::{Board, GameBoard};
+
+// ミスしたときに選ぶ候補の数 (評価の高い順)
+const MISTAKE_CANDIDATES: usize = 5;
+// 先読みするのは今の盤面で評価の高いこの数の置き方だけ
+const SEARCH_BEAM: usize = 8;
+// 先読みは設定しても次のミノ 2 個まで
+pub const MAX_SEARCH_DEPTH: usize = 3;
+// 1 フレームで置き方を探す回数 深く読むほど考えるのに何フレームもかかる
+const SEARCH_STEPS_PER_FRAME: usize = 4;
+// ミノの順番とは別の乱数にする
+const AI_SEED_SALT: u64 = 0x51_7cc1_b727_220a;
+
+// コンピューターの強さ
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AiDifficulty {
+    // 1 秒に置くミノの数の上限 0 なら上限なし
+    pub pps: f64,
+    // 何個先のミノまで読むか (1 なら今のミノだけ)
+    pub depth: usize,
+    // 一番いい置き方を選ばない確率 (%)
+    pub mistake_percent: i64,
+}
+
+// メニューで順番に切り替える
+pub const AI_PRESETS: [(&str, AiDifficulty); 3] = [
+    (
+        "EASY",
+        AiDifficulty {
+            pps: 0.8,
+            depth: 1,
+            mistake_percent: 25,
+        },
+    ),
+    (
+        "NORMAL",
+        AiDifficulty {
+            pps: 1.5,
+            depth: 1,
+            mistake_percent: 5,
+        },
+    ),
+    (
+        "HARD",
+        AiDifficulty {
+            pps: 2.5,
+            depth: 2,
+            mistake_percent: 0,
+        },
+    ),
+];
+
+impl Default for AiDifficulty {
+    fn default() -> Self {
+        return AI_PRESETS[1].1;
+    }
+}
+
+impl AiDifficulty {
+    // プリセットから変えていたら CUSTOM
+    pub fn name(&self) -> &'static str {
+        return AI_PRESETS
+            .iter()
+            .find(|(_, difficulty)| difficulty == self)
+            .map_or("CUSTOM", |(name, _)| name);
+    }
+
+    pub fn from_name(name: &str) -> Option<AiDifficulty> {
+        return AI_PRESETS
+            .iter()
+            .find(|(preset, _)| preset.eq_ignore_ascii_case(name))
+            .map(|(_, difficulty)| *difficulty);
+    }
+
+    // 次のミノを置くまでに空けるフレーム数
+    fn frames_per_piece(&self) -> i64 {
+        if self.pps <= 0. {
+            return 0;
+        }
+        return (FRAMES_PER_SECOND as f64 / self.pps).round() as i64;
+    }
+}
+
+// 盤面の評価の重み 大きいほど良い盤面
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AiWeights {
+    pub height: f64,
+    pub holes: f64,
+    pub bumpiness: f64,
+    // この高さを超えた分だけ危ないとみなす
+    pub danger_height: i64,
+    pub danger: f64,
+    pub attack: f64,
+    // 攻撃にならない消し方
+    pub lines: f64,
+    // 最後の盤面で続いている Back-to-Back とコンボ (1 回ごと)
+    pub back_to_back: f64,
+    pub combo: f64,
+}
+
+impl Default for AiWeights {
+    fn default() -> Self {
+        return AiWeights {
+            height: -0.5,
+            holes: -4.,
+            bumpiness: -0.4,
+            danger_height: 12,
+            danger: -3.,
+            attack: 2.,
+            lines: 0.3,
+            back_to_back: 1.5,
+            combo: 0.5,
+        };
+    }
+}
+
+// 盤面の形だけの評価
+fn evaluate_board(board: &Board, weights: &AiWeights) -> f64 {
+    let start_pos = HEIGHT_PADDING / 2;
+    let mut heights = [0; MAX_WIDTH];
+    let mut holes = 0;
+    for (i, height) in heights.iter_mut().enumerate() {
+        let x = start_pos + i;
+        let top = (start_pos..(start_pos + MAX_HEIGHT))
+            .rev()
+            .find(|y| board[*y][x] != 0);
+        if let Some(top) = top {
+            *height = (top + 1 - start_pos) as i64;
+            holes += (start_pos..top).filter(|y| board[*y][x] == 0).count() as i64;
+        }
+    }
+
+    let total: i64 = heights.iter().sum();
+    let max = heights.iter().copied().max().unwrap_or(0);
+    let bumpiness: i64 = heights.windows(2).map(|w| (w[0] - w[1]).abs()).sum();
+    let danger = (max - weights.danger_height).max(0);
+    return weights.height * total as f64
+        + weights.holes * holes as f64
+        + weights.bumpiness * bumpiness as f64
+        + weights.danger * danger as f64;
+}
+
+// 続けて消している数と Back-to-Back の状態 Score と同じように進める
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Chain {
+    combo: i64,
+    back_to_back: bool,
+}
+
+impl Chain {
+    fn new(score: &Score) -> Self {
+        return Chain {
+            combo: score.combo,
+            back_to_back: score.back_to_back,
+        };
+    }
+
+    // 消した後の状態と、その消し方に Back-to-Back が付くか
+    fn after(&self, lock: &LockEvent) -> (Chain, bool) {
+        if lock.lines == 0 {
+            let chain = Chain {
+                combo: -1,
+                back_to_back: self.back_to_back,
+            };
+            return (chain, false);
+        }
+        let is_difficult = lock.lines >= 4 || lock.spin != SpinType::None;
+        let chain = Chain {
+            combo: self.combo + 1,
+            back_to_back: is_difficult,
+        };
+        return (chain, is_difficult && self.back_to_back);
+    }
+}
+
+// ラインを消したときの評価 盤面の形は最後の盤面でだけ見る
+// chain は消した後の状態
+fn evaluate_clear(
+    lock: &LockEvent,
+    chain: &Chain,
+    back_to_back: bool,
+    rules: &GarbageRules,
+    weights: &AiWeights,
+) -> f64 {
+    let attack = rules.attack_lines(lock, chain.combo, back_to_back);
+    if attack > 0 {
+        return weights.attack * attack as f64;
+    }
+    return weights.lines * lock.lines as f64;
+}
+
+// 最後の盤面で続いている Back-to-Back とコンボの評価
+fn evaluate_chain(chain: &Chain, weights: &AiWeights) -> f64 {
+    let mut value = weights.combo * chain.combo.max(0) as f64;
+    if chain.back_to_back {
+        value += weights.back_to_back;
+    }
+    return value;
+}
+
+// 評価した置き方 board は固定してラインを消した後
+#[derive(Clone)]
+struct Candidate {
+    value: f64,
+    clear: f64,
+    placement: Placement,
+    board: GameBoard,
+    chain: Chain,
+    // この置き方の後に先読みするミノ
+    rest: Vec<TetrominoType>,
+}
+
+// game は落下速度などのルールを探索に使う
+struct AiSearch<'g> {
+    weights: &'g AiWeights,
+    rules: &'g GarbageRules,
+    game: &'g Game,
+}
+
+impl<'g> AiSearch<'g> {
+    // 先読みしないときの評価
+    fn evaluate(&self, placement: Placement, chain: &Chain, rest: &[TetrominoType]) -> Candidate {
+        let (board, lock) = placement.lock();
+        let (chain, back_to_back) = chain.after(&lock);
+        let clear = evaluate_clear(&lock, &chain, back_to_back, self.rules, self.weights);
+        return Candidate {
+            value: clear
+                + evaluate_board(&board.board, self.weights)
+                + evaluate_chain(&chain, self.weights),
+            clear: clear,
+            placement: placement,
+            board: board,
+            chain: chain,
+            rest: rest.to_vec(),
+        };
+    }
+
+    // 評価の高い順に並べて、先読みする分だけ残す
+    fn sort(&self, candidates: &mut Vec<Candidate>) {
+        candidates.sort_by(|a, b| b.value.total_cmp(&a.value));
+        if candidates
+            .iter()
+            .any(|candidate| !candidate.rest.is_empty())
+        {
+            candidates.truncate(SEARCH_BEAM);
+        }
+    }
+
+    // 次のミノの置き方 (先読みする分だけ) 出てきた時点で詰むなら空
+    fn expand(&self, candidate: &Candidate) -> Vec<Candidate> {
+        let (typ, rest) = match candidate.rest.split_first() {
+            Some(split) => split,
+            None => return Vec::new(),
+        };
+        let mut next = candidate.board.clone();
+        next.init_tetrimono(*typ);
+        if next.check_block_out().is_some() {
+            return Vec::new();
+        }
+        let mut candidates = find_spawn_placements(self.game, &candidate.board, *typ)
+            .into_iter()
+            .map(|placement| self.evaluate(placement, &candidate.chain, rest))
+            .collect();
+        self.sort(&mut candidates);
+        return candidates;
+    }
+
+    // 先読みの評価はその先で一番よくなる置き方の評価にする
+    fn deepen(&self, candidate: &mut Candidate) {
+        if candidate.rest.is_empty() {
+            return;
+        }
+        let mut candidates = self.expand(candidate);
+        for next in candidates.iter_mut() {
+            self.deepen(next);
+        }
+        candidate.value = candidate.clear + best_value(&candidates);
+    }
+}
+
+fn best_value(candidates: &[Candidate]) -> f64 {
+    return candidates
+        .iter()
+        .map(|candidate| candidate.value)
+        .max_by(f64::total_cmp)
+        .unwrap_or(f64::MIN);
+}
+
+// 考えている途中の置き方 先読みは 1 フレームに SEARCH_STEPS_PER_FRAME 回ずつ進める
+#[derive(Clone)]
+struct PendingPlan {
+    // 考え始めたときのゲーム
+    game: Game,
+    candidates: Vec<Candidate>,
+    searched: usize,
+    // 先読みしている candidates[searched] の次のミノの置き方と、読み終えた数
+    children: Option<Vec<Candidate>>,
+    child: usize,
+    frames: i64,
+}
+
+// 1 人分のゲームを見て、毎フレームの入力を返す
+#[derive(Clone)]
+pub struct AiController {
+    pub difficulty: AiDifficulty,
+    pub weights: AiWeights,
+    pub rules: GarbageRules,
+    rng: Pcg32,
+    plan: InputPlayer,
+    pending: Option<PendingPlan>,
+    // 今のミノを待ち始めたときに置き終えていたミノの数
+    current_piece: Option<i64>,
+    // 考え始めるまで待つフレーム数
+    wait: i64,
+    // 前のミノを考えて動かすのにかかったフレーム数 (待ち時間から引く)
+    last_plan_frames: i64,
+}
+
+impl AiController {
+    pub fn new(difficulty: AiDifficulty, rules: GarbageRules, seed: u64) -> Self {
+        return AiController {
+            difficulty: difficulty,
+            weights: AiWeights::default(),
+            rules: rules,
+            rng: Pcg32::new(seed ^ AI_SEED_SALT),
+            plan: InputPlayer::default(),
+            pending: None,
+            current_piece: None,
+            wait: 0,
+            last_plan_frames: 0,
+        };
+    }
+
+    pub fn next_inputs(&mut self, game: &Game) -> Inputs {
+        if game.is_over() || game.board.tetrimono.is_none() {
+            return Inputs::default();
+        }
+
+        // 待ってから考えるので、待っている間に落ちても計画はずれない
+        if self.current_piece != Some(game.score.pieces) {
+            self.current_piece = Some(game.score.pieces);
+            self.plan = InputPlayer::default();
+            self.pending = None;
+            self.wait = self.difficulty.frames_per_piece() - self.last_plan_frames;
+        }
+
+        if self.wait > 0 {
+            self.wait -= 1;
+            return Inputs::default();
+        }
+
+        // 計画を使い切っても置けていなければ考え直す
+        if self.plan.is_finished() {
+            if self.pending.is_none() {
+                self.pending = Some(self.start_plan(game));
+            }
+            let (candidates, is_stale) = match self.search() {
+                Some(result) => result,
+                None => return Inputs::default(),
+            };
+            let plan = self.finish_plan(game, candidates, is_stale);
+            // ボタンは押して離すので 1 操作 2 フレーム
+            self.last_plan_frames += 2 * plan.len() as i64;
+            self.plan = InputPlayer::new(plan, game.last_inputs());
+        }
+
+        return self.plan.next_inputs(&game.board);
+    }
+
+    // ホールドしない場合とする場合の置き方を全部並べる 先読みは search でする
+    fn start_plan(&self, game: &Game) -> PendingPlan {
+        let ai = AiSearch {
+            weights: &self.weights,
+            rules: &self.rules,
+            game: game,
+        };
+        let depth = self.difficulty.depth.clamp(1, MAX_SEARCH_DEPTH);
+        let queue = &game.queue.queue;
+
+        // ホールドが空なら次のミノが出てきて、その分だけ先読みがずれる
+        let hold_queue = match game.stock.mono {
+            Some(_) => &queue[..],
+            None => queue.get(1..).unwrap_or(&[]),
+        };
+        let queue = &queue[..(depth - 1).min(queue.len())];
+        let hold_queue = &hold_queue[..(depth - 1).min(hold_queue.len())];
+
+        let chain = Chain::new(&game.score);
+        let mut candidates = find_placements(game, true)
+            .into_iter()
+            .map(|placement| {
+                let rest = if placement.uses_hold {
+                    hold_queue
+                } else {
+                    queue
+                };
+                ai.evaluate(placement, &chain, rest)
+            })
+            .collect();
+        ai.sort(&mut candidates);
+        return PendingPlan {
+            game: game.clone(),
+            candidates: candidates,
+            searched: 0,
+            children: None,
+            child: 0,
+            frames: 0,
+        };
+    }
+
+    // 先読みを少しずつ進めて、全部読み終えたら評価の高い順の置き方を返す
+    // 考え始めてからフレームが進んでいたら true も返す
+    fn search(&mut self) -> Option<(Vec<Candidate>, bool)> {
+        let pending = self.pending.as_mut()?;
+        let ai = AiSearch {
+            weights: &self.weights,
+            rules: &self.rules,
+            game: &pending.game,
+        };
+        // 置き方を 1 回探すのを 1 歩とする 先読みしない置き方は数えない
+        let mut steps = 0;
+        while steps < SEARCH_STEPS_PER_FRAME {
+            let candidate = match pending.candidates.get_mut(pending.searched) {
+                Some(candidate) => candidate,
+                None => break,
+            };
+            if candidate.rest.is_empty() {
+                pending.searched += 1;
+                continue;
+            }
+            match pending.children.as_mut() {
+                None => {
+                    pending.children = Some(ai.expand(candidate));
+                    pending.child = 0;
+                    steps += 1;
+                }
+                Some(children) if pending.child < children.len() => {
+                    let child = &mut children[pending.child];
+                    if !child.rest.is_empty() {
+                        ai.deepen(child);
+                        steps += 1;
+                    }
+                    pending.child += 1;
+                }
+                Some(children) => {
+                    candidate.value = candidate.clear + best_value(children);
+                    pending.children = None;
+                    pending.searched += 1;
+                }
+            }
+        }
+        pending.frames += 1;
+        if pending.searched < pending.candidates.len() {
+            return None;
+        }
+
+        let mut pending = self.pending.take()?;
+        pending
+            .candidates
+            .sort_by(|a, b| b.value.total_cmp(&a.value));
+        self.last_plan_frames = pending.frames - 1;
+        return Some((pending.candidates, pending.frames > 1));
+    }
+
+    // 評価の高い置き方 (ミスしたら上の方から適当に選ぶ) の入力を返す
+    // 考えている間にミノが落ちていたら、今のゲームで同じ場所に置ける入力を探し直す
+    fn finish_plan(
+        &mut self,
+        game: &Game,
+        mut candidates: Vec<Candidate>,
+        is_stale: bool,
+    ) -> Vec<MoveInput> {
+        if (self.rng.below(100) as i64) < self.difficulty.mistake_percent {
+            let index = self
+                .rng
+                .below(candidates.len().min(MISTAKE_CANDIDATES) as u32)
+                as usize;
+            if index < candidates.len() {
+                let candidate = candidates.remove(index);
+                candidates.insert(0, candidate);
+            }
+        }
+
+        if !is_stale {
+            return match candidates.into_iter().next() {
+                Some(candidate) => candidate.placement.inputs,
+                None => vec![MoveInput::HardDrop],
+            };
+        }
+
+        let placements = find_placements(game, true);
+        for candidate in candidates.iter() {
+            let cells = candidate.placement.cells();
+            let reachable = placements.iter().find(|placement| {
+                placement.uses_hold == candidate.placement.uses_hold
+                    && placement.spin == candidate.placement.spin
+                    && placement.cells() == cells
+            });
+            if let Some(placement) = reachable {
+                return placement.inputs.clone();
+            }
+        }
+        return vec![MoveInput::HardDrop];
+    }
+}
+
+#[test]
+fn ai_clears_lines_without_topping_out() {
+    use super::config::GameSettings;
+
+    let mut game = Game::new(GameSettings {
+        seed: Some(7),
+        ..Default::default()
+    });
+    let difficulty = AiDifficulty {
+        pps: 0.,
+        depth: 1,
+        mistake_percent: 0,
+    };
+    let mut ai = AiController::new(difficulty, GarbageRules::default(), 7);
+    while game.score.pieces < 40 && !game.is_over() {
+        let inputs = ai.next_inputs(&game);
+        game.step(inputs);
+    }
+    assert!(!game.is_over());
+    assert!(game.score.lines >= 10);
+}
+
+#[test]
+fn chain_follows_the_score() {
+    let mut score = Score::new(1, 10);
+    let mut chain = Chain::new(&score);
+    let locks = [
+        (4, SpinType::None),
+        (2, SpinType::Full),
+        (1, SpinType::None),
+        (0, SpinType::None),
+        (4, SpinType::None),
+    ];
+    for (lines, spin) in locks {
+        let lock = LockEvent {
+            spin: spin,
+            lines: lines,
+            is_perfect_clear: false,
+        };
+        chain = chain.after(&lock).0;
+        score.on_lock(lines, spin, false);
+        assert_eq!(chain, Chain::new(&score));
+    }
+}
+
+#[test]
+fn deep_search_is_spread_over_frames() {
+    use super::config::GameSettings;
+
+    let game = Game::new(GameSettings {
+        seed: Some(7),
+        ..Default::default()
+    });
+    let difficulty = AiDifficulty {
+        pps: 0.,
+        depth: MAX_SEARCH_DEPTH,
+        mistake_percent: 0,
+    };
+    let mut ai = AiController::new(difficulty, GarbageRules::default(), 7);
+    let mut frames = 1;
+    while ai.next_inputs(&game) == Inputs::default() && ai.pending.is_some() {
+        frames += 1;
+    }
+    // 8 個の置き方をそれぞれ 2 手先まで読むので 1 フレームでは終わらない
+    assert!(frames > 1);
+    assert!(!ai.plan.is_finished());
+}
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+use super::counter::LockResetRule;
+use super::handling::Handling;
+use super::mode::GameMode;
+use super::randomizer::RandomizerType;
+use super::rotation::RotationSystemType;
+
+pub const TETRIMONO_MATRIX_SIZE: usize = 4;
+pub const MAX_HEIGHT: usize = 22;
+// 上の 2 段は見えない (バッファ)
+pub const VISIBLE_HEIGHT: usize = 20;
+pub const MAX_WIDTH: usize = 10;
+pub const MIN_QUEUE_MONO_COUNT: usize = 7;
+pub const MAX_GRAVITY: f64 = 20.;
+pub const LINES_PER_LEVEL: i64 = 10;
+// キューに必ず残っている数までしか先読みできない
+pub const MAX_NEXT_COUNT: usize = MIN_QUEUE_MONO_COUNT;
+pub const FRAMES_PER_SECOND: i64 = 60;
+pub const INITIAL_LOCK_DELAY_FRAMES: i64 = 30;
+pub const LOCK_RESET_LIMIT: i64 = 15;
+pub const INITIAL_DAS_FRAMES: i64 = 10;
+pub const INITIAL_ARR_FRAMES: i64 = 2;
+pub const INITIAL_SOFT_DROP_FACTOR: i64 = 20;
+pub const INITIAL_DAS_CUT_FRAMES: i64 = 1;
+pub const INITIAL_MONO_POSITION: (usize, usize) = (20, 5);
+pub const HEIGHT_WITH_PADDING: usize = MAX_HEIGHT + HEIGHT_PADDING;
+pub const WIDTH_WITH_PADDING: usize = MAX_WIDTH + WIDTH_PADDING;
+pub const WIDTH_PADDING: usize = 4;
+pub const HEIGHT_PADDING: usize = 4;
+// せり上がりのブロック
+pub const GARBAGE_CELL: u8 = 8;
+
+// ゲームのルール (表示に関する設定はフロントエンド側で持つ)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GameSettings {
+    // 古いリプレイには無いので無ければ Endless にする
+    #[serde(default)]
+    pub mode: GameMode,
+    pub rotation_system: RotationSystemType,
+    pub randomizer: RandomizerType,
+    pub lock_delay: i64,
+    pub lock_reset_rule: LockResetRule,
+    pub lock_reset_limit: i64,
+    pub handling: Handling,
+    pub start_level: i64,
+    pub lines_per_level: i64,
+    // ミノが一部でも見えない段に固定されたらゲームオーバーにするか
+    pub partial_lock_out: bool,
+    // ミノの順番を決めるシード None ならゲームごとにランダム
+    pub seed: Option<u64>,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        return GameSettings {
+            mode: GameMode::default(),
+            rotation_system: RotationSystemType::default(),
+            randomizer: RandomizerType::default(),
+            lock_delay: INITIAL_LOCK_DELAY_FRAMES,
+            lock_reset_rule: LockResetRule::default(),
+            lock_reset_limit: LOCK_RESET_LIMIT,
+            handling: Handling::default(),
+            start_level: 1,
+            lines_per_level: LINES_PER_LEVEL,
+            partial_lock_out: false,
+            seed: None,
+        };
+    }
+}
@@ -0,0 +1,257 @@
+use serde::{Deserialize, Serialize};
+
+use super::config::{FRAMES_PER_SECOND, MAX_GRAVITY};
+
+// 自然落下 1 フレームあたりに落ちるマス数を積み上げて、1 マス分たまったら落とす
+#[derive(Clone)]
+pub struct FrameCounter {
+    pub elapsed: f64,
+    pub gravity: f64,
+}
+
+impl Default for FrameCounter {
+    fn default() -> Self {
+        return FrameCounter {
+            elapsed: 0.,
+            gravity: gravity_for_level(1),
+        };
+    }
+}
+
+impl FrameCounter {
+    pub fn tick(&mut self) {
+        self.elapsed += self.gravity;
+    }
+
+    pub fn set_gravity(&mut self, gravity: f64) {
+        self.gravity = gravity.min(MAX_GRAVITY);
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = 0.;
+    }
+
+    // このフレームで落とすマス数
+    pub fn take_cells(&mut self) -> usize {
+        let cells = self.elapsed.floor();
+        self.elapsed -= cells;
+        return cells as usize;
+    }
+}
+
+// ガイドラインの落下速度 (1 マス落ちるのにかかる秒数) = (0.8 - (level - 1) * 0.007) ^ (level - 1)
+// 60 fps でのマス / フレームに直し、20G で打ち止めにする
+pub fn gravity_for_level(level: i64) -> f64 {
+    let level = level.max(1) as f64;
+    let seconds_per_cell = (0.8 - (level - 1.) * 0.007).powf(level - 1.);
+    return (1. / (seconds_per_cell * 60.)).min(MAX_GRAVITY);
+}
+
+// ゲーム中だけ進む時計 (ポーズ中は止まる)
+#[derive(Clone, Default)]
+pub struct GameClock {
+    pub frames: i64,
+}
+
+impl GameClock {
+    pub fn tick(&mut self) {
+        self.frames += 1;
+    }
+
+    // mm:ss.cc
+    pub fn format(&self) -> String {
+        return format_frames(self.frames);
+    }
+}
+
+pub fn format_frames(frames: i64) -> String {
+    let centis = frames * 100 / FRAMES_PER_SECOND;
+    return format!(
+        "{:02}:{:02}.{:02}",
+        centis / 6000,
+        centis / 100 % 60,
+        centis % 100
+    );
+}
+
+// mm:ss.mmm スプリントの記録用 (1 フレーム単位)
+pub fn format_frames_millis(frames: i64) -> String {
+    let millis = frames * 1000 / FRAMES_PER_SECOND;
+    return format!(
+        "{:02}:{:02}.{:03}",
+        millis / 60000,
+        millis / 1000 % 60,
+        millis % 1000
+    );
+}
+
+// 自己ベストとの差 +s.mmm / -s.mmm
+pub fn format_frame_diff(frames: i64) -> String {
+    let sign = if frames < 0 { "-" } else { "+" };
+    let millis = frames.abs() * 1000 / FRAMES_PER_SECOND;
+    return format!("{}{}.{:03}", sign, millis / 1000, millis % 1000);
+}
+
+// 接地してから固定されるまでの猶予をどうリセットするか
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LockResetRule {
+    // 移動・回転でリセット、ただし回数制限あり (ガイドライン)
+    #[default]
+    MoveReset,
+    // 移動・回転で無制限にリセット
+    Infinity,
+    // 最下段を更新したときだけリセット
+    StepReset,
+}
+
+impl LockResetRule {
+    pub fn name(self) -> &'static str {
+        return match self {
+            LockResetRule::MoveReset => "move",
+            LockResetRule::Infinity => "infinity",
+            LockResetRule::StepReset => "step",
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        return match name.to_lowercase().as_str() {
+            "move" => Some(LockResetRule::MoveReset),
+            "infinity" => Some(LockResetRule::Infinity),
+            "step" => Some(LockResetRule::StepReset),
+            _ => None,
+        };
+    }
+}
+
+#[derive(Clone)]
+pub struct LockCounter {
+    pub elapsed: i64,
+    pub target: i64,
+    pub rule: LockResetRule,
+    pub reset_count: i64,
+    pub reset_limit: i64,
+    pub lowest_row: usize,
+    // 今の最下段に接地したことがあるか 移動回数は接地してから数える
+    pub is_landed: bool,
+    pub is_forced: bool,
+}
+
+impl LockCounter {
+    pub fn new(target: i64, rule: LockResetRule, reset_limit: i64) -> Self {
+        return LockCounter {
+            elapsed: 0,
+            target: target,
+            rule: rule,
+            reset_count: 0,
+            reset_limit: reset_limit,
+            lowest_row: usize::MAX,
+            is_landed: false,
+            is_forced: false,
+        };
+    }
+
+    // 新しいミノが出たとき
+    pub fn init(&mut self, row: usize) {
+        self.elapsed = 0;
+        self.reset_count = 0;
+        self.lowest_row = row;
+        self.is_landed = false;
+        self.is_forced = false;
+    }
+
+    // 接地している間だけ進める
+    pub fn tick(&mut self) {
+        self.elapsed += 1;
+        self.is_landed = true;
+    }
+
+    // 最下段を更新したら猶予も移動回数も戻す
+    pub fn step(&mut self, row: usize) {
+        if row >= self.lowest_row {
+            return;
+        }
+        self.lowest_row = row;
+        self.elapsed = 0;
+        self.reset_count = 0;
+        self.is_landed = false;
+    }
+
+    // 移動・回転に成功したとき
+    pub fn on_action(&mut self) {
+        match self.rule {
+            // 空中での移動は回数に入れない
+            LockResetRule::MoveReset if !self.is_landed => {}
+            LockResetRule::MoveReset => {
+                if self.reset_count < self.reset_limit {
+                    self.elapsed = 0;
+                    self.reset_count += 1;
+                }
+            }
+            LockResetRule::Infinity => self.elapsed = 0,
+            LockResetRule::StepReset => {}
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        if self.is_forced {
+            return true;
+        }
+
+        // 移動回数を使い切ったら接地した瞬間に固定する
+        if self.rule == LockResetRule::MoveReset && self.reset_count >= self.reset_limit {
+            return true;
+        }
+
+        return self.target <= self.elapsed;
+    }
+
+    pub fn force_finish(&mut self) {
+        self.is_forced = true;
+    }
+}
+
+#[test]
+fn move_reset_is_limited() {
+    let mut counter = LockCounter::new(2, LockResetRule::MoveReset, 2);
+    counter.init(20);
+
+    counter.tick();
+    counter.on_action();
+    assert_eq!(counter.elapsed, 0);
+    counter.tick();
+    counter.on_action();
+    assert!(counter.is_finished());
+
+    // 下に落ちたら移動回数も戻る
+    counter.step(19);
+    assert!(!counter.is_finished());
+}
+
+#[test]
+fn move_reset_ignores_moves_in_the_air() {
+    let mut counter = LockCounter::new(30, LockResetRule::MoveReset, 15);
+    counter.init(20);
+
+    for _ in 0..20 {
+        counter.on_action();
+    }
+    assert_eq!(counter.reset_count, 0);
+
+    // 接地してからも猶予が残っている
+    counter.tick();
+    assert!(!counter.is_finished());
+    counter.on_action();
+    assert_eq!(counter.reset_count, 1);
+}
+
+#[test]
+fn gravity_reaches_20g() {
+    assert!((gravity_for_level(1) - 1. / 60.).abs() < 1e-9);
+    assert!(gravity_for_level(10) < 1.);
+    assert_eq!(gravity_for_level(20), MAX_GRAVITY);
+
+    let mut counter = FrameCounter::default();
+    counter.set_gravity(gravity_for_level(20));
+    counter.tick();
+    assert_eq!(counter.take_cells(), 20);
+}
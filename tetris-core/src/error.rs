@@ -0,0 +1,29 @@
+use std::fmt;
+
+// ファイルを読み書きするときのエラー
+#[derive(Debug)]
+pub enum FileError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            FileError::Io(error) => write!(f, "io error: {}", error),
+            FileError::Json(error) => write!(f, "json error: {}", error),
+        };
+    }
+}
+
+impl From<std::io::Error> for FileError {
+    fn from(error: std::io::Error) -> Self {
+        return FileError::Io(error);
+    }
+}
+
+impl From<serde_json::Error> for FileError {
+    fn from(error: serde_json::Error) -> Self {
+        return FileError::Json(error);
+    }
+}
@@ -0,0 +1,61 @@
+use super::mode::GameEnd;
+use super::score::SpinType;
+
+// ミノを固定してラインを消した後に出す
+#[derive(Debug, Clone, Copy)]
+pub struct LockEvent {
+    pub spin: SpinType,
+    pub lines: u8,
+    pub is_perfect_clear: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreKind {
+    SoftDrop {
+        cells: i64,
+    },
+    HardDrop {
+        cells: i64,
+    },
+    LineClear {
+        lines: u8,
+        spin: SpinType,
+        back_to_back: bool,
+    },
+    Combo {
+        count: i64,
+    },
+    PerfectClear {
+        lines: u8,
+    },
+}
+
+// 得点が入るたびに出す
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreEvent {
+    pub kind: ScoreKind,
+    pub points: i64,
+}
+
+// ゲームが終わったときに出す (目標を達成したときも)
+#[derive(Debug, Clone, Copy)]
+pub struct GameOverEvent {
+    pub cause: GameEnd,
+}
+
+// 相殺しきれなかった分を相手に送る
+#[derive(Debug, Clone, Copy)]
+pub struct AttackEvent {
+    pub lines: i64,
+}
+
+// Game::step が 1 フレームの間に起きたことを順番に返す
+#[derive(Debug, Clone, Copy)]
+pub enum GameEvent {
+    // 盤面・ホールド・ネクストのどれかが変わった
+    BoardUpdate,
+    Lock(LockEvent),
+    Score(ScoreEvent),
+    Attack(AttackEvent),
+    GameOver(GameOverEvent),
+}
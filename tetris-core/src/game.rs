@@ -0,0 +1,629 @@
+use serde::{Deserialize, Serialize};
+
+use super::config::GameSettings;
+use super::counter::{FrameCounter, GameClock, LockCounter};
+use super::event::{AttackEvent, GameEvent, GameOverEvent, LockEvent};
+use super::garbage::{GarbageHoles, GarbageRules, HolePlacement, PendingGarbage};
+use super::handling::{AutoShift, ShiftAction};
+use super::mode::{GameEnd, DIG_VISIBLE_LINES, SPLIT_LINES};
+use super::random::random_seed;
+use super::score::Score;
+use super::tetris::{
+    GameBoard, MoveDirection, RotateDirection, StockTetrimono, TetrimonoQueue, TopOut,
+};
+
+// 1 フレーム分のボタンの状態 (押されているかどうか)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Inputs {
+    pub left: bool,
+    pub right: bool,
+    pub soft_drop: bool,
+    pub hard_drop: bool,
+    pub rotate_right: bool,
+    pub rotate_left: bool,
+    pub rotate_half: bool,
+    pub hold: bool,
+}
+
+impl Inputs {
+    // リプレイに保存するときは 1 ボタン 1 ビットにする
+    pub fn to_bits(self) -> u8 {
+        let buttons = [
+            self.left,
+            self.right,
+            self.soft_drop,
+            self.hard_drop,
+            self.rotate_right,
+            self.rotate_left,
+            self.rotate_half,
+            self.hold,
+        ];
+        let mut bits = 0;
+        for (i, button) in buttons.iter().enumerate() {
+            if *button {
+                bits |= 1 << i;
+            }
+        }
+        return bits;
+    }
+
+    pub fn from_bits(bits: u8) -> Inputs {
+        let button = |i: usize| bits & (1 << i) != 0;
+        return Inputs {
+            left: button(0),
+            right: button(1),
+            soft_drop: button(2),
+            hard_drop: button(3),
+            rotate_right: button(4),
+            rotate_left: button(5),
+            rotate_half: button(6),
+            hold: button(7),
+        };
+    }
+
+    // 前のフレームでは押されていなかったボタンだけを残す
+    pub fn pressed_since(self, prev: Inputs) -> Inputs {
+        return Inputs {
+            left: self.left && !prev.left,
+            right: self.right && !prev.right,
+            soft_drop: self.soft_drop && !prev.soft_drop,
+            hard_drop: self.hard_drop && !prev.hard_drop,
+            rotate_right: self.rotate_right && !prev.rotate_right,
+            rotate_left: self.rotate_left && !prev.rotate_left,
+            rotate_half: self.rotate_half && !prev.rotate_half,
+            hold: self.hold && !prev.hold,
+        };
+    }
+
+    // どちらかで押されているボタン
+    pub fn union(self, other: Inputs) -> Inputs {
+        return Inputs {
+            left: self.left || other.left,
+            right: self.right || other.right,
+            soft_drop: self.soft_drop || other.soft_drop,
+            hard_drop: self.hard_drop || other.hard_drop,
+            rotate_right: self.rotate_right || other.rotate_right,
+            rotate_left: self.rotate_left || other.rotate_left,
+            rotate_half: self.rotate_half || other.rotate_half,
+            hold: self.hold || other.hold,
+        };
+    }
+}
+
+// 1 人分のゲーム step を呼んだ分だけ進む
+#[derive(Clone)]
+pub struct Game {
+    pub settings: GameSettings,
+    // 同じシードと入力なら同じゲームになる
+    pub seed: u64,
+    pub board: GameBoard,
+    pub queue: TetrimonoQueue,
+    pub stock: StockTetrimono,
+    pub counter: FrameCounter,
+    pub lock_counter: LockCounter,
+    pub auto_shift: AutoShift,
+    pub score: Score,
+    pub clock: GameClock,
+    pub end: Option<GameEnd>,
+    // SPLIT_LINES ごとに消し終えたときの clock.frames
+    pub splits: Vec<i64>,
+    pub garbage_holes: GarbageHoles,
+    // 対戦の攻撃とせり上がりのルール 1 人用ではせり上がりを受けないので使わない
+    pub garbage_rules: GarbageRules,
+    // 掘るモードでまだ盤面に出していないせり上がりの段数
+    pub garbage_to_insert: i64,
+    // 相手から受けてまだせり上がっていない攻撃 (古い順)
+    pub pending_garbage: Vec<PendingGarbage>,
+    last_inputs: Inputs,
+}
+
+impl Game {
+    pub fn new(settings: GameSettings) -> Self {
+        let settings = GameSettings {
+            mode: settings.mode.clamped(),
+            ..settings
+        };
+        let seed = settings.seed.unwrap_or_else(random_seed);
+        let mut queue = TetrimonoQueue::new(seed, settings.randomizer);
+
+        let mut board = GameBoard {
+            rotation_system: settings.rotation_system,
+            ..Default::default()
+        };
+
+        // 掘るモードは画面に収まる分だけ先に積んでおく
+        let mut garbage_holes = GarbageHoles::new(seed, settings.mode.messiness().unwrap_or(0));
+        let mut garbage_to_insert = settings.mode.dig_lines().unwrap_or(0);
+        while garbage_to_insert > 0 && (board.count_garbage_lines() as i64) < DIG_VISIBLE_LINES {
+            board.insert_garbage_line(garbage_holes.next_hole());
+            garbage_to_insert -= 1;
+        }
+        board.init_tetrimono(queue.dequeue());
+
+        let mut lock_counter = LockCounter::new(
+            settings.lock_delay,
+            settings.lock_reset_rule,
+            settings.lock_reset_limit,
+        );
+        lock_counter.init(board.cursor.0);
+
+        let mut score = Score::new(settings.start_level, settings.lines_per_level);
+        score.max_level = settings.mode.max_level();
+        let mut counter = FrameCounter::default();
+        counter.set_gravity(settings.mode.gravity(&score));
+
+        return Game {
+            settings: settings,
+            seed: seed,
+            board: board,
+            queue: queue,
+            stock: StockTetrimono::default(),
+            counter: counter,
+            lock_counter: lock_counter,
+            auto_shift: AutoShift::default(),
+            score: score,
+            clock: GameClock::default(),
+            end: None,
+            splits: Vec::new(),
+            garbage_holes: garbage_holes,
+            garbage_rules: GarbageRules::default(),
+            garbage_to_insert: garbage_to_insert,
+            pending_garbage: Vec::new(),
+            last_inputs: Inputs::default(),
+        };
+    }
+
+    pub fn is_over(&self) -> bool {
+        return self.end.is_some();
+    }
+
+    // まだ消していないせり上がりの段数 (盤面にあるものと出していないもの)
+    pub fn garbage_left(&self) -> i64 {
+        return self.board.count_garbage_lines() as i64 + self.garbage_to_insert;
+    }
+
+    // 1 フレーム進める 入力を処理してから時間を進める
+    pub fn step(&mut self, inputs: Inputs) -> Vec<GameEvent> {
+        return self.advance(inputs, true);
+    }
+
+    // 置き方を探すとき用 時計・モード・せり上がりは止めたまま、操作中のミノだけ 1 フレーム進める
+    pub fn step_piece(&mut self, inputs: Inputs) -> Vec<GameEvent> {
+        return self.advance(inputs, false);
+    }
+
+    fn advance(&mut self, inputs: Inputs, runs_clock: bool) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        if self.is_over() {
+            return events;
+        }
+
+        let pressed = inputs.pressed_since(self.last_inputs);
+        self.last_inputs = inputs;
+
+        self.handle_inputs(inputs, pressed, &mut events);
+        if runs_clock && !self.is_over() {
+            self.tick_clock(&mut events);
+        }
+        if !self.is_over() {
+            self.tick_piece(&mut events);
+        }
+        return events;
+    }
+
+    // 相手からの攻撃 待ち時間が過ぎてから、ラインを消さずにミノを置いたときにせり上がる
+    pub fn receive_garbage(&mut self, lines: i64) {
+        if lines > 0 {
+            self.pending_garbage.push(PendingGarbage {
+                lines: lines,
+                delay: self.garbage_rules.delay_frames,
+                hole: None,
+            });
+        }
+    }
+
+    pub fn pending_garbage_lines(&self) -> i64 {
+        return self
+            .pending_garbage
+            .iter()
+            .map(|garbage| garbage.lines)
+            .sum();
+    }
+
+    // 次にミノを置いたらせり上がる段数 (上限は考えない)
+    pub fn ready_garbage_lines(&self) -> i64 {
+        return self
+            .pending_garbage
+            .iter()
+            .filter(|garbage| garbage.is_ready())
+            .map(|garbage| garbage.lines)
+            .sum();
+    }
+
+    // 前のフレームのボタンの状態
+    pub fn last_inputs(&self) -> Inputs {
+        return self.last_inputs;
+    }
+
+    // ポーズから戻ったときなどに押しっぱなしの扱いを持ち越さない
+    pub fn release_inputs(&mut self) {
+        self.auto_shift = AutoShift::default();
+        self.last_inputs = Inputs::default();
+    }
+
+    fn handle_inputs(&mut self, inputs: Inputs, pressed: Inputs, events: &mut Vec<GameEvent>) {
+        let handling = self.settings.handling;
+        let mut is_updated = false;
+
+        if pressed.hold && self.stock.hold(&mut self.board, &mut self.queue) {
+            self.counter.reset();
+            self.lock_counter.init(self.board.cursor.0);
+            is_updated = true;
+            // ホールドから出したミノも重なっていたら終わり
+            if let Some(cause) = self.board.check_block_out() {
+                events.push(GameEvent::BoardUpdate);
+                self.end(GameEnd::TopOut(cause), events);
+                return;
+            }
+        }
+
+        let rotate = if pressed.rotate_right {
+            Some(RotateDirection::Right)
+        } else if pressed.rotate_left {
+            Some(RotateDirection::Left)
+        } else if pressed.rotate_half {
+            Some(RotateDirection::Half)
+        } else {
+            None
+        };
+
+        if let Some(dir) = rotate {
+            if self.board.try_rotate(dir) {
+                self.lock_counter.on_action();
+                is_updated = true;
+            }
+            self.auto_shift.cut(&handling);
+        }
+
+        let shift = self.auto_shift.shift(
+            &handling,
+            (pressed.left, inputs.left),
+            (pressed.right, inputs.right),
+        );
+        if let Some((dir, action)) = shift {
+            if self.shift_tetrimono(dir, action) > 0 {
+                self.lock_counter.on_action();
+                is_updated = true;
+            }
+        }
+
+        let soft_drop = self.auto_shift.soft_drop(
+            &handling,
+            pressed.soft_drop,
+            inputs.soft_drop,
+            self.counter.gravity,
+        );
+        if let Some(action) = soft_drop {
+            let cells = self.shift_tetrimono(MoveDirection::Down, action);
+            if cells > 0 {
+                events.push(GameEvent::Score(self.score.on_drop(cells, false)));
+                self.counter.reset();
+                is_updated = true;
+            }
+        }
+
+        if pressed.hard_drop {
+            let cells = self.shift_tetrimono(MoveDirection::Down, ShiftAction::ToWall);
+            events.push(GameEvent::Score(self.score.on_drop(cells, true)));
+            // 次の時間経過ですぐに固定させる
+            self.lock_counter.force_finish();
+            self.auto_shift.cut(&handling);
+            is_updated = true;
+        }
+
+        if is_updated {
+            events.push(GameEvent::BoardUpdate);
+        }
+    }
+
+    // 時計とモードのルール (時間切れ、せり上がり)
+    fn tick_clock(&mut self, events: &mut Vec<GameEvent>) {
+        self.clock.tick();
+        let time_limit = self.settings.mode.time_limit();
+        if time_limit.is_some_and(|frames| self.clock.frames >= frames) {
+            self.end(GameEnd::TimeUp, events);
+            return;
+        }
+
+        for garbage in self.pending_garbage.iter_mut() {
+            garbage.delay -= 1;
+        }
+
+        let interval = self.settings.mode.garbage_interval();
+        if interval.is_some_and(|frames| frames > 0 && self.clock.frames % frames == 0) {
+            events.push(GameEvent::BoardUpdate);
+            if let Some(cause) = self
+                .board
+                .insert_garbage_line(self.garbage_holes.next_hole())
+            {
+                self.end(GameEnd::TopOut(cause), events);
+            }
+        }
+    }
+
+    // 落下と固定までの猶予
+    fn tick_piece(&mut self, events: &mut Vec<GameEvent>) {
+        self.counter.tick();
+        self.lock_counter.step(self.board.cursor.0);
+
+        // can drop
+        let cells = self.counter.take_cells();
+        if self.shift_tetrimono(MoveDirection::Down, ShiftAction::Cells(cells)) > 0 {
+            events.push(GameEvent::BoardUpdate);
+        }
+
+        if self.board.simulate_move(MoveDirection::Down) {
+            // 空中にいる間は固定しない
+            return;
+        }
+
+        self.lock_counter.tick();
+        if !self.lock_counter.is_finished() {
+            return;
+        }
+
+        // cannot drop
+        events.push(GameEvent::BoardUpdate);
+        self.lock(events);
+    }
+
+    fn lock(&mut self, events: &mut Vec<GameEvent>) {
+        let spin = self.board.detect_spin();
+        let lock_out = self.board.check_lock_out(self.settings.partial_lock_out);
+        self.board.write_tetrimono();
+
+        let clear_count = self.board.clear_lines();
+        let is_perfect_clear = clear_count > 0 && self.board.is_perfect_clear();
+        let lock = LockEvent {
+            spin: spin,
+            lines: clear_count,
+            is_perfect_clear: is_perfect_clear,
+        };
+        events.push(GameEvent::Lock(lock));
+        let was_back_to_back = self.score.back_to_back;
+        for event in self.score.on_lock(clear_count, spin, is_perfect_clear) {
+            events.push(GameEvent::Score(event));
+        }
+        // レベルが変わったら落下速度を変える
+        self.counter
+            .set_gravity(self.settings.mode.gravity(&self.score));
+
+        // clear_lines が返したライン数で 10 ラインごとの時間を残す
+        while (self.splits.len() as i64 + 1) * SPLIT_LINES <= self.score.lines {
+            self.splits.push(self.clock.frames);
+        }
+
+        // 掘った分だけ下から補充する
+        while self.garbage_to_insert > 0
+            && (self.board.count_garbage_lines() as i64) < DIG_VISIBLE_LINES
+        {
+            self.garbage_to_insert -= 1;
+            if let Some(cause) = self
+                .board
+                .insert_garbage_line(self.garbage_holes.next_hole())
+            {
+                self.end(GameEnd::TopOut(cause), events);
+                return;
+            }
+        }
+
+        // 目標に届いたらそのミノが見えない位置でも達成を優先する
+        if self
+            .settings
+            .mode
+            .is_goal_reached(&self.score, self.garbage_left())
+        {
+            self.end(GameEnd::Goal, events);
+            return;
+        }
+
+        if let Some(cause) = lock_out {
+            self.end(GameEnd::TopOut(cause), events);
+            return;
+        }
+
+        // 消したら受けている攻撃と相殺して余りを送る 消さなかったら受けている攻撃がせり上がる
+        if clear_count > 0 {
+            let back_to_back = was_back_to_back && self.score.back_to_back;
+            let mut attack = self
+                .garbage_rules
+                .attack_lines(&lock, self.score.combo, back_to_back);
+            if self.garbage_rules.cancel_before_receive {
+                attack = self.offset_garbage(attack);
+            }
+            if attack > 0 {
+                events.push(GameEvent::Attack(AttackEvent { lines: attack }));
+            }
+        } else if let Some(cause) = self.raise_garbage() {
+            self.end(GameEnd::TopOut(cause), events);
+            return;
+        }
+
+        // ラインを消してから次のミノを出す
+        self.board.init_tetrimono(self.queue.dequeue());
+        self.stock.release();
+        self.lock_counter.init(self.board.cursor.0);
+        self.counter.reset();
+
+        if let Some(cause) = self.board.check_block_out() {
+            self.end(GameEnd::TopOut(cause), events);
+        }
+    }
+
+    // 受けている攻撃を古い順に打ち消して、残った攻撃力を返す
+    fn offset_garbage(&mut self, attack: i64) -> i64 {
+        let mut attack = attack;
+        while attack > 0 && !self.pending_garbage.is_empty() {
+            let offset = attack.min(self.pending_garbage[0].lines);
+            attack -= offset;
+            self.pending_garbage[0].lines -= offset;
+            if self.pending_garbage[0].lines == 0 {
+                self.pending_garbage.remove(0);
+            }
+        }
+        return attack;
+    }
+
+    // 待ち時間が過ぎた攻撃を古い順に 1 段ずつ、上限までせり上げる
+    fn raise_garbage(&mut self) -> Option<TopOut> {
+        let mut cap = self.garbage_rules.cap_per_piece.unwrap_or(i64::MAX);
+        while cap > 0 {
+            let garbage = match self.pending_garbage.first_mut() {
+                Some(garbage) if garbage.is_ready() => garbage,
+                _ => break,
+            };
+            let hole = match self.garbage_rules.hole_placement {
+                HolePlacement::PerAttack => *garbage
+                    .hole
+                    .get_or_insert_with(|| self.garbage_holes.next_hole()),
+                HolePlacement::PerLine => self.garbage_holes.next_hole(),
+            };
+            garbage.lines -= 1;
+            if garbage.lines == 0 {
+                self.pending_garbage.remove(0);
+            }
+            cap -= 1;
+
+            if let Some(cause) = self.board.insert_garbage_line(hole) {
+                return Some(cause);
+            }
+        }
+        return None;
+    }
+
+    fn end(&mut self, cause: GameEnd, events: &mut Vec<GameEvent>) {
+        self.end = Some(cause);
+        events.push(GameEvent::GameOver(GameOverEvent { cause: cause }));
+    }
+
+    // 動けなくなるか指定のマス数まで動かす 動いたマス数を返す
+    fn shift_tetrimono(&mut self, dir: MoveDirection, action: ShiftAction) -> i64 {
+        let mut cells = 0;
+        if action.cells() == 0 {
+            return cells;
+        }
+        while self.board.simulate_move(dir) {
+            self.board.apply_move(dir);
+            cells += 1;
+            if cells as usize >= action.cells() {
+                break;
+            }
+        }
+        return cells;
+    }
+}
+
+#[test]
+fn hard_drop_locks_and_spawns_next() {
+    let mut game = Game::new(GameSettings::default());
+    let next = game.queue.queue[0];
+
+    let events = game.step(Inputs {
+        hard_drop: true,
+        ..Default::default()
+    });
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, GameEvent::Lock(_))));
+    assert_eq!(game.board.tetrimono.unwrap().typ, next);
+    assert_eq!(game.score.pieces, 1);
+
+    // 押しっぱなしでは 2 回目のハードドロップにならない
+    game.step(Inputs {
+        hard_drop: true,
+        ..Default::default()
+    });
+    assert_eq!(game.score.pieces, 1);
+}
+
+#[test]
+fn hold_into_blocks_is_block_out() {
+    let mut game = Game::new(GameSettings::default());
+    // 出現位置のまわりを埋める
+    for y in 18..24 {
+        for x in 2..12 {
+            game.board.board[y][x] = 8;
+        }
+    }
+
+    let events = game.step(Inputs {
+        hold: true,
+        ..Default::default()
+    });
+    assert!(events.iter().any(|event| matches!(
+        event,
+        GameEvent::GameOver(GameOverEvent {
+            cause: GameEnd::TopOut(TopOut::BlockOut)
+        })
+    )));
+    assert!(game.is_over());
+}
+
+#[test]
+fn sprint_ends_when_the_goal_is_reached() {
+    use super::mode::GameMode;
+
+    let mut game = Game::new(GameSettings {
+        mode: GameMode::Sprint { lines: 1 },
+        ..Default::default()
+    });
+    game.board.fill_row_for_i(2);
+
+    let events = game.step(Inputs {
+        hard_drop: true,
+        ..Default::default()
+    });
+    assert!(events.iter().any(|event| matches!(
+        event,
+        GameEvent::GameOver(GameOverEvent {
+            cause: GameEnd::Goal
+        })
+    )));
+    assert_eq!(game.splits, Vec::<i64>::new());
+    assert!(game.is_over());
+}
+
+#[test]
+fn ultra_ends_when_the_time_is_up() {
+    use super::mode::GameMode;
+
+    let mut game = Game::new(GameSettings {
+        mode: GameMode::Ultra { seconds: 1 },
+        ..Default::default()
+    });
+    for _ in 0..59 {
+        game.step(Inputs::default());
+    }
+    assert!(!game.is_over());
+
+    game.step(Inputs::default());
+    assert_eq!(game.end, Some(GameEnd::TimeUp));
+}
+
+#[test]
+fn survival_with_zero_seconds_still_raises_garbage() {
+    use super::config::FRAMES_PER_SECOND;
+    use super::mode::GameMode;
+
+    let mut game = Game::new(GameSettings {
+        mode: GameMode::Survival {
+            seconds: 0,
+            messiness: 0,
+        },
+        ..Default::default()
+    });
+    for _ in 0..FRAMES_PER_SECOND {
+        game.step(Inputs::default());
+    }
+    assert_eq!(game.board.count_garbage_lines(), 1);
+}
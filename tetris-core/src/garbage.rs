@@ -0,0 +1,185 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::config::MAX_WIDTH;
+use super::error::FileError;
+use super::event::LockEvent;
+use super::random::Pcg32;
+use super::score::SpinType;
+
+// せり上がりの穴をどの単位で動かすか
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HolePlacement {
+    // 1 回の攻撃の分は同じ列 攻撃ごとに確率で列を変える
+    #[default]
+    PerAttack,
+    // 1 段ごとに確率で列を変える
+    PerLine,
+}
+
+// 対戦の攻撃とせり上がりのルール
+// ファイルに書かなかった項目はガイドラインの値になる
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GarbageRules {
+    // 相手に送るライン数 添字は消したライン数 (表より多く消したら最後の値)
+    pub line_clear: Vec<i64>,
+    pub mini_spin: Vec<i64>,
+    pub spin: Vec<i64>,
+    pub back_to_back: i64,
+    // 添字は REN の数 (最初に消したときが 0)
+    pub combo: Vec<i64>,
+    pub perfect_clear: i64,
+    // 受けてからせり上がれるようになるまでのフレーム数
+    pub delay_frames: i64,
+    // 1 回ミノを置いたときにせり上がる段数の上限 None なら全部
+    pub cap_per_piece: Option<i64>,
+    pub hole_placement: HolePlacement,
+    // 前と違う列に穴を空ける確率 (%)
+    pub hole_change_percent: i64,
+    // 消したときに受けている攻撃と相殺してから余りを送るか
+    // false なら攻撃は全部相手に送り、受けている攻撃は減らさずに残す
+    // どちらでも受けている攻撃がせり上がるのはラインを消さずに置いたときだけ
+    pub cancel_before_receive: bool,
+}
+
+impl Default for GarbageRules {
+    fn default() -> Self {
+        return GarbageRules {
+            line_clear: vec![0, 0, 1, 2, 4],
+            mini_spin: vec![0, 0, 1, 0, 0],
+            spin: vec![0, 2, 4, 6, 0],
+            back_to_back: 1,
+            combo: vec![0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5],
+            perfect_clear: 10,
+            delay_frames: 0,
+            cap_per_piece: None,
+            hole_placement: HolePlacement::PerAttack,
+            hole_change_percent: 100,
+            cancel_before_receive: true,
+        };
+    }
+}
+
+impl GarbageRules {
+    // combo と back_to_back はこのミノで消した後の値
+    pub fn attack_lines(&self, lock: &LockEvent, combo: i64, back_to_back: bool) -> i64 {
+        if lock.lines == 0 {
+            return 0;
+        }
+
+        let index = lock.lines as usize;
+        let mut lines = match lock.spin {
+            SpinType::None => lookup(&self.line_clear, index),
+            SpinType::Mini => lookup(&self.mini_spin, index),
+            SpinType::Full => lookup(&self.spin, index),
+        };
+        if back_to_back {
+            lines += self.back_to_back;
+        }
+        if combo > 0 {
+            lines += lookup(&self.combo, combo as usize);
+        }
+        if lock.is_perfect_clear {
+            lines += self.perfect_clear;
+        }
+        return lines;
+    }
+
+    pub fn load(path: &Path) -> Result<GarbageRules, FileError> {
+        return Ok(serde_json::from_str(&fs::read_to_string(path)?)?);
+    }
+}
+
+// 表より大きい添字は最後の値にする
+fn lookup(table: &[i64], index: usize) -> i64 {
+    return table.get(index).or(table.last()).copied().unwrap_or(0);
+}
+
+// 相手から受けてまだせり上がっていない 1 回分の攻撃
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingGarbage {
+    pub lines: i64,
+    // あと何フレームでせり上がれるようになるか
+    pub delay: i64,
+    // 上限で途中までせり上がった攻撃は残りも同じ列に穴を空ける
+    pub hole: Option<usize>,
+}
+
+impl PendingGarbage {
+    pub fn is_ready(&self) -> bool {
+        return self.delay <= 0;
+    }
+}
+
+// ミノの順番とは別の列にして、せり上がりの有無でミノの順番が変わらないようにする
+const GARBAGE_SEED_SALT: u64 = 0x9e37_79b9_7f4a_7c15;
+
+// せり上がりの段の穴の位置を決める
+#[derive(Clone)]
+pub struct GarbageHoles {
+    rng: Pcg32,
+    hole: Option<usize>,
+    // 前の段と違う列に穴を空ける確率 (%) 0 なら縦一列、100 なら毎段変わる
+    pub messiness: i64,
+}
+
+impl GarbageHoles {
+    pub fn new(seed: u64, messiness: i64) -> Self {
+        return GarbageHoles {
+            rng: Pcg32::new(seed ^ GARBAGE_SEED_SALT),
+            hole: None,
+            messiness: messiness.clamp(0, 100),
+        };
+    }
+
+    pub fn next_hole(&mut self) -> usize {
+        let hole = match self.hole {
+            None => self.rng.below(MAX_WIDTH as u32) as usize,
+            Some(hole) if (self.rng.below(100) as i64) < self.messiness => {
+                // 同じ列を引かないように残りの列から選ぶ
+                let offset = 1 + self.rng.below(MAX_WIDTH as u32 - 1) as usize;
+                (hole + offset) % MAX_WIDTH
+            }
+            Some(hole) => hole,
+        };
+        self.hole = Some(hole);
+        return hole;
+    }
+}
+
+#[test]
+fn messiness_decides_how_often_the_hole_moves() {
+    let mut clean = GarbageHoles::new(3, 0);
+    let first = clean.next_hole();
+    assert!((0..20).all(|_| clean.next_hole() == first));
+
+    let mut messy = GarbageHoles::new(3, 100);
+    let mut last = messy.next_hole();
+    for _ in 0..20 {
+        let hole = messy.next_hole();
+        assert_ne!(hole, last);
+        last = hole;
+    }
+}
+
+#[test]
+fn rules_file_overrides_only_what_it_lists() {
+    let rules: GarbageRules =
+        serde_json::from_str(r#"{ "line_clear": [0, 1, 2, 3], "perfect_clear": 6 }"#).unwrap();
+    assert_eq!(rules.combo, GarbageRules::default().combo);
+
+    let tetris = LockEvent {
+        spin: SpinType::None,
+        lines: 4,
+        is_perfect_clear: true,
+    };
+    // 表より多く消したら最後の値を使う
+    assert_eq!(rules.attack_lines(&tetris, 0, false), 3 + 6);
+    assert_eq!(
+        GarbageRules::default().attack_lines(&tetris, 2, true),
+        4 + 1 + 1 + 10
+    );
+}
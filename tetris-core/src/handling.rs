@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+
+use super::config::{
+    INITIAL_ARR_FRAMES, INITIAL_DAS_CUT_FRAMES, INITIAL_DAS_FRAMES, INITIAL_SOFT_DROP_FACTOR,
+};
+use super::tetris::MoveDirection;
+
+// キーを押しっぱなしにしたときの挙動 (プレイヤーごとの設定)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Handling {
+    // 押してから連続移動が始まるまでのフレーム数
+    pub das: i64,
+    // 連続移動の間隔 0 なら壁まで一気に動かす
+    pub arr: i64,
+    // 自然落下の何倍の速さでソフトドロップするか 0 なら一気に底まで落とす
+    pub soft_drop_factor: i64,
+    // 回転・ハードドロップの後に連続移動を止めるフレーム数
+    pub das_cut: i64,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        return Handling {
+            das: INITIAL_DAS_FRAMES,
+            arr: INITIAL_ARR_FRAMES,
+            soft_drop_factor: INITIAL_SOFT_DROP_FACTOR,
+            das_cut: INITIAL_DAS_CUT_FRAMES,
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShiftAction {
+    Step,
+    Cells(usize),
+    ToWall,
+}
+
+impl ShiftAction {
+    pub fn cells(self) -> usize {
+        return match self {
+            ShiftAction::Step => 1,
+            ShiftAction::Cells(cells) => cells,
+            ShiftAction::ToWall => usize::MAX,
+        };
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct AutoShift {
+    // 最後に押された方向
+    pub direction: Option<MoveDirection>,
+    pub held: i64,
+    pub repeat: i64,
+    pub das_cut: i64,
+    pub drop_progress: f64,
+}
+
+impl AutoShift {
+    // left / right はそれぞれ (just_pressed, pressed)
+    pub fn shift(
+        &mut self,
+        handling: &Handling,
+        left: (bool, bool),
+        right: (bool, bool),
+    ) -> Option<(MoveDirection, ShiftAction)> {
+        if left.0 {
+            self.press(MoveDirection::Left);
+        }
+        if right.0 {
+            self.press(MoveDirection::Right);
+        }
+
+        let direction = self.direction?;
+        let (is_pressed, is_other_pressed, other) = match direction {
+            MoveDirection::Left => (left.1, right.1, MoveDirection::Right),
+            _ => (right.1, left.1, MoveDirection::Left),
+        };
+
+        // 離されたらもう片方が押されていればそちらに戻す
+        if !is_pressed {
+            if !is_other_pressed {
+                self.direction = None;
+                return None;
+            }
+            self.press(other);
+        }
+
+        let direction = self.direction?;
+        self.held += 1;
+        if self.held == 1 {
+            return Some((direction, ShiftAction::Step));
+        }
+
+        if self.das_cut > 0 {
+            self.das_cut -= 1;
+            return None;
+        }
+
+        if self.held <= handling.das {
+            return None;
+        }
+
+        if handling.arr == 0 {
+            return Some((direction, ShiftAction::ToWall));
+        }
+
+        if self.repeat > 0 {
+            self.repeat -= 1;
+            return None;
+        }
+
+        self.repeat = handling.arr - 1;
+        return Some((direction, ShiftAction::Step));
+    }
+
+    // このフレームでソフトドロップするマス数
+    pub fn soft_drop(
+        &mut self,
+        handling: &Handling,
+        just_pressed: bool,
+        pressed: bool,
+        gravity: f64,
+    ) -> Option<ShiftAction> {
+        if !pressed {
+            return None;
+        }
+
+        if handling.soft_drop_factor == 0 {
+            return Some(ShiftAction::ToWall);
+        }
+
+        // 押した瞬間に 1 マス落とし、あとは自然落下の soft_drop_factor 倍で落とす
+        if just_pressed {
+            self.drop_progress = 1.;
+        } else {
+            self.drop_progress += gravity * handling.soft_drop_factor as f64;
+        }
+
+        let cells = self.drop_progress.floor();
+        if cells < 1. {
+            return None;
+        }
+        self.drop_progress -= cells;
+        return Some(ShiftAction::Cells(cells as usize));
+    }
+
+    // 回転・ハードドロップの後は少しの間だけ連続移動を止める
+    pub fn cut(&mut self, handling: &Handling) {
+        self.das_cut = handling.das_cut;
+    }
+
+    fn press(&mut self, direction: MoveDirection) {
+        self.direction = Some(direction);
+        self.held = 0;
+        self.repeat = 0;
+    }
+}
+
+#[test]
+fn last_pressed_direction_wins() {
+    let handling = Handling {
+        das: 2,
+        arr: 0,
+        ..Default::default()
+    };
+    let mut auto_shift = AutoShift::default();
+
+    let action = auto_shift.shift(&handling, (true, true), (false, false));
+    assert_eq!(action, Some((MoveDirection::Left, ShiftAction::Step)));
+    assert_eq!(
+        auto_shift.shift(&handling, (false, true), (false, false)),
+        None
+    );
+    let action = auto_shift.shift(&handling, (false, true), (false, false));
+    assert_eq!(action, Some((MoveDirection::Left, ShiftAction::ToWall)));
+
+    let action = auto_shift.shift(&handling, (false, true), (true, true));
+    assert_eq!(action, Some((MoveDirection::Right, ShiftAction::Step)));
+
+    // 右を離すと左に戻る
+    let action = auto_shift.shift(&handling, (false, true), (false, false));
+    assert_eq!(action, Some((MoveDirection::Left, ShiftAction::Step)));
+}
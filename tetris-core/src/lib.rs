@@ -0,0 +1,29 @@
+// 本体のクレートと同じ書き方 (return の明示、x: x の初期化、添字のループ) をそのまま使う
+#![allow(
+    clippy::needless_return,
+    clippy::redundant_field_names,
+    clippy::needless_range_loop
+)]
+
+// Bevy に依存しないゲームのルール
+// フロントエンドは Game::step に毎フレームの入力を渡して、返ってきたイベントを描画に使う
+
+pub mod ai;
+pub mod config;
+pub mod counter;
+pub mod error;
+pub mod event;
+pub mod game;
+pub mod garbage;
+pub mod handling;
+pub mod mode;
+pub mod movegen;
+pub mod random;
+pub mod randomizer;
+pub mod record;
+pub mod replay;
+pub mod rotation;
+pub mod score;
+pub mod tetrimono;
+pub mod tetris;
+pub mod versus;
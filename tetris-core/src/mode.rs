@@ -0,0 +1,355 @@
+use serde::{Deserialize, Serialize};
+
+use super::config::FRAMES_PER_SECOND;
+use super::counter::gravity_for_level;
+use super::score::Score;
+use super::tetris::TopOut;
+
+// スプリットを残す間隔
+pub const SPLIT_LINES: i64 = 10;
+pub const SPRINT_LINES: [i64; 3] = [20, 40, 100];
+const DEFAULT_SPRINT_LINES: i64 = 40;
+const DEFAULT_ULTRA_SECONDS: i64 = 120;
+const DEFAULT_MARATHON_LINES: i64 = 150;
+// マラソンはこのレベルより速くならない
+pub const MARATHON_MAX_LEVEL: i64 = 15;
+pub const DIG_LINES: [i64; 2] = [10, 100];
+// 掘るモードで一度に盤面に出しておくせり上がりの段数
+pub const DIG_VISIBLE_LINES: i64 = 10;
+const DEFAULT_DIG_MESSINESS: i64 = 100;
+const DEFAULT_SURVIVAL_SECONDS: i64 = 5;
+const DEFAULT_SURVIVAL_MESSINESS: i64 = 30;
+// モードの数値の上限 秒数をフレーム数にしてもあふれない
+const MAX_PARAM: i64 = 100_000;
+
+// 何を目指して遊ぶか
+// 落下速度・終わる条件・HUD の中身はここで決めて、Game はそれに従うだけにする
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameMode {
+    // 積めなくなるまで続ける
+    #[default]
+    Endless,
+    // 決められたライン数を消すまでの時間を競う
+    Sprint {
+        lines: i64,
+    },
+    // 決められた時間内の得点を競う
+    Ultra {
+        seconds: i64,
+    },
+    // レベルを上げながら決められたライン数まで生き残る
+    Marathon {
+        lines: i64,
+    },
+    // 最初から積まれているせり上がりを全部消すまでの時間を競う
+    // messiness は前の段と違う列に穴を空ける確率 (%)
+    Dig {
+        lines: i64,
+        messiness: i64,
+    },
+    // seconds ごとにせり上がってくる中で生き残る
+    Survival {
+        seconds: i64,
+        messiness: i64,
+    },
+}
+
+// メニューで順番に切り替える
+const PRESETS: [GameMode; 9] = [
+    GameMode::Endless,
+    GameMode::Sprint {
+        lines: SPRINT_LINES[0],
+    },
+    GameMode::Sprint {
+        lines: SPRINT_LINES[1],
+    },
+    GameMode::Sprint {
+        lines: SPRINT_LINES[2],
+    },
+    GameMode::Ultra {
+        seconds: DEFAULT_ULTRA_SECONDS,
+    },
+    GameMode::Marathon {
+        lines: DEFAULT_MARATHON_LINES,
+    },
+    GameMode::Dig {
+        lines: DIG_LINES[0],
+        messiness: DEFAULT_DIG_MESSINESS,
+    },
+    GameMode::Dig {
+        lines: DIG_LINES[1],
+        messiness: DEFAULT_DIG_MESSINESS,
+    },
+    GameMode::Survival {
+        seconds: DEFAULT_SURVIVAL_SECONDS,
+        messiness: DEFAULT_SURVIVAL_MESSINESS,
+    },
+];
+
+// HUD に並べる値
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HudItem {
+    Score,
+    Lines,
+    Level,
+    Time,
+    // 制限時間の残り
+    TimeLeft,
+    // 残っているせり上がりの段数
+    Garbage,
+    Pieces,
+    // 最後のスプリット
+    Split,
+}
+
+impl GameMode {
+    pub fn name(&self) -> String {
+        return match self {
+            GameMode::Endless => String::from("ENDLESS"),
+            GameMode::Sprint { lines } => format!("SPRINT {}L", lines),
+            GameMode::Ultra { seconds } => {
+                format!("ULTRA {}:{:02}", seconds / 60, seconds % 60)
+            }
+            GameMode::Marathon { lines } => format!("MARATHON {}L", lines),
+            GameMode::Dig { lines, .. } => format!("DIG {}L", lines),
+            GameMode::Survival { .. } => String::from("SURVIVAL"),
+        };
+    }
+
+    // リプレイやコマンドラインで使う名前
+    pub fn id(&self) -> &'static str {
+        return match self {
+            GameMode::Endless => "endless",
+            GameMode::Sprint { .. } => "sprint",
+            GameMode::Ultra { .. } => "ultra",
+            GameMode::Marathon { .. } => "marathon",
+            GameMode::Dig { .. } => "dig",
+            GameMode::Survival { .. } => "survival",
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        return match name.to_lowercase().as_str() {
+            "endless" => Some(GameMode::Endless),
+            "sprint" => Some(GameMode::Sprint {
+                lines: DEFAULT_SPRINT_LINES,
+            }),
+            "ultra" => Some(GameMode::Ultra {
+                seconds: DEFAULT_ULTRA_SECONDS,
+            }),
+            "marathon" => Some(GameMode::Marathon {
+                lines: DEFAULT_MARATHON_LINES,
+            }),
+            "dig" => Some(GameMode::Dig {
+                lines: DIG_LINES[0],
+                messiness: DEFAULT_DIG_MESSINESS,
+            }),
+            "survival" => Some(GameMode::Survival {
+                seconds: DEFAULT_SURVIVAL_SECONDS,
+                messiness: DEFAULT_SURVIVAL_MESSINESS,
+            }),
+            _ => None,
+        };
+    }
+
+    // モードごとの数値 (スプリント・マラソン・掘るモードはライン数、ウルトラとサバイバルは秒数)
+    pub fn param(&self) -> i64 {
+        return match self {
+            GameMode::Endless => 0,
+            GameMode::Sprint { lines } => *lines,
+            GameMode::Ultra { seconds } => *seconds,
+            GameMode::Marathon { lines } => *lines,
+            GameMode::Dig { lines, .. } => *lines,
+            GameMode::Survival { seconds, .. } => *seconds,
+        };
+    }
+
+    // param が秒数かどうか
+    pub fn is_param_seconds(&self) -> bool {
+        return matches!(self, GameMode::Ultra { .. } | GameMode::Survival { .. });
+    }
+
+    pub fn with_param(self, param: i64) -> GameMode {
+        let param = param.clamp(1, MAX_PARAM);
+        return match self {
+            GameMode::Endless => GameMode::Endless,
+            GameMode::Sprint { .. } => GameMode::Sprint { lines: param },
+            GameMode::Ultra { .. } => GameMode::Ultra { seconds: param },
+            GameMode::Marathon { .. } => GameMode::Marathon { lines: param },
+            GameMode::Dig { messiness, .. } => GameMode::Dig {
+                lines: param,
+                messiness: messiness,
+            },
+            GameMode::Survival { messiness, .. } => GameMode::Survival {
+                seconds: param,
+                messiness: messiness,
+            },
+        };
+    }
+
+    // せり上がりのあるモードだけ Some
+    pub fn messiness(&self) -> Option<i64> {
+        return match self {
+            GameMode::Dig { messiness, .. } | GameMode::Survival { messiness, .. } => {
+                Some(*messiness)
+            }
+            _ => None,
+        };
+    }
+
+    pub fn with_messiness(self, messiness: i64) -> GameMode {
+        let messiness = messiness.clamp(0, 100);
+        return match self {
+            GameMode::Dig { lines, .. } => GameMode::Dig {
+                lines: lines,
+                messiness: messiness,
+            },
+            GameMode::Survival { seconds, .. } => GameMode::Survival {
+                seconds: seconds,
+                messiness: messiness,
+            },
+            mode => mode,
+        };
+    }
+
+    // 数値を遊べる範囲に収める 読み込んだ設定もここを通してから遊ぶ
+    pub fn clamped(self) -> GameMode {
+        let mode = self.with_param(self.param());
+        return match self.messiness() {
+            Some(messiness) => mode.with_messiness(messiness),
+            None => mode,
+        };
+    }
+
+    // 最初に積んでおくせり上がりの段数 (全部で何段掘るか)
+    pub fn dig_lines(&self) -> Option<i64> {
+        return match self {
+            GameMode::Dig { lines, .. } => Some(*lines),
+            _ => None,
+        };
+    }
+
+    // このフレーム数ごとに 1 段せり上がる
+    pub fn garbage_interval(&self) -> Option<i64> {
+        return match self {
+            GameMode::Survival { seconds, .. } => seconds.checked_mul(FRAMES_PER_SECOND),
+            _ => None,
+        };
+    }
+
+    // ここまで消したら終わり
+    pub fn goal_lines(&self) -> Option<i64> {
+        return match self {
+            GameMode::Sprint { lines } | GameMode::Marathon { lines } => Some(*lines),
+            _ => None,
+        };
+    }
+
+    // garbage_left はまだ消していないせり上がりの段数 (出していない分も含む)
+    pub fn is_goal_reached(&self, score: &Score, garbage_left: i64) -> bool {
+        if self.dig_lines().is_some() {
+            return garbage_left == 0;
+        }
+        return self.goal_lines().is_some_and(|lines| score.lines >= lines);
+    }
+
+    // このフレーム数が経ったら終わり
+    pub fn time_limit(&self) -> Option<i64> {
+        return match self {
+            GameMode::Ultra { seconds } => seconds.checked_mul(FRAMES_PER_SECOND),
+            _ => None,
+        };
+    }
+
+    pub fn max_level(&self) -> Option<i64> {
+        return match self {
+            GameMode::Marathon { .. } => Some(MARATHON_MAX_LEVEL),
+            _ => None,
+        };
+    }
+
+    // エンドレスとマラソン以外は最後まで開始レベルの速さのまま
+    pub fn gravity(&self, score: &Score) -> f64 {
+        return match self {
+            GameMode::Sprint { .. }
+            | GameMode::Ultra { .. }
+            | GameMode::Dig { .. }
+            | GameMode::Survival { .. } => gravity_for_level(score.start_level),
+            _ => gravity_for_level(score.level),
+        };
+    }
+
+    pub fn hud_items(&self) -> &'static [HudItem] {
+        return match self {
+            GameMode::Endless | GameMode::Marathon { .. } => {
+                &[HudItem::Score, HudItem::Lines, HudItem::Level]
+            }
+            GameMode::Sprint { .. } => &[HudItem::Time, HudItem::Lines, HudItem::Split],
+            GameMode::Ultra { .. } => &[HudItem::TimeLeft, HudItem::Score, HudItem::Lines],
+            GameMode::Dig { .. } => &[HudItem::Time, HudItem::Garbage, HudItem::Pieces],
+            GameMode::Survival { .. } => &[HudItem::Time, HudItem::Score, HudItem::Lines],
+        };
+    }
+
+    pub fn next(self) -> GameMode {
+        let index = PRESETS.iter().position(|mode| *mode == self);
+        return match index {
+            Some(index) => PRESETS[(index + 1) % PRESETS.len()],
+            None => PRESETS[0],
+        };
+    }
+
+    pub fn prev(self) -> GameMode {
+        let index = PRESETS.iter().position(|mode| *mode == self);
+        return match index {
+            Some(index) => PRESETS[(index + PRESETS.len() - 1) % PRESETS.len()],
+            None => PRESETS[0],
+        };
+    }
+}
+
+// ゲームが終わった理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEnd {
+    TopOut(TopOut),
+    // モードの目標ライン数を消した
+    Goal,
+    // 制限時間まで生き残った
+    TimeUp,
+}
+
+impl GameEnd {
+    pub fn name(&self) -> &'static str {
+        return match self {
+            GameEnd::TopOut(cause) => cause.name(),
+            GameEnd::Goal => "FINISH",
+            GameEnd::TimeUp => "TIME UP",
+        };
+    }
+
+    pub fn is_cleared(&self) -> bool {
+        return matches!(self, GameEnd::Goal | GameEnd::TimeUp);
+    }
+}
+
+#[test]
+fn out_of_range_params_are_clamped() {
+    let mode = GameMode::Survival {
+        seconds: 0,
+        messiness: 200,
+    };
+    assert_eq!(
+        mode.clamped(),
+        GameMode::Survival {
+            seconds: 1,
+            messiness: 100,
+        }
+    );
+
+    let mode = GameMode::Ultra { seconds: i64::MAX };
+    assert_eq!(mode.time_limit(), None);
+    assert_eq!(
+        mode.clamped().time_limit(),
+        Some(MAX_PARAM * FRAMES_PER_SECOND)
+    );
+}
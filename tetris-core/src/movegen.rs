@@ -0,0 +1,415 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::event::{GameEvent, LockEvent};
+use super::game::{Game, Inputs};
+use super::score::SpinType;
+use super::tetrimono::TetrominoType;
+use super::tetris::{GameBoard, MoveDirection, RotateDirection};
+
+// ミノを置くまでの 1 回分の入力
+// DasLeft / DasRight / SoftDrop は動けなくなるまで押しっぱなしにする
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MoveInput {
+    Hold,
+    Left,
+    Right,
+    DasLeft,
+    DasRight,
+    RotateRight,
+    RotateLeft,
+    RotateHalf,
+    SoftDrop,
+    HardDrop,
+}
+
+// 探索で試す順番 同じ手数なら先に見つかった方を残す
+const SEARCH_INPUTS: [MoveInput; 8] = [
+    MoveInput::Left,
+    MoveInput::Right,
+    MoveInput::DasLeft,
+    MoveInput::DasRight,
+    MoveInput::RotateRight,
+    MoveInput::RotateLeft,
+    MoveInput::RotateHalf,
+    MoveInput::SoftDrop,
+];
+
+// 入力の並びを毎フレームのボタンの状態に直す
+// 同じボタンを続けて押すと長押しになるので、押したら次のフレームは離す
+#[derive(Clone, Default)]
+pub struct InputPlayer {
+    inputs: VecDeque<MoveInput>,
+    last_inputs: Inputs,
+}
+
+impl InputPlayer {
+    // last_inputs はゲームが前のフレームに受け取ったボタン
+    pub fn new(inputs: Vec<MoveInput>, last_inputs: Inputs) -> Self {
+        return InputPlayer {
+            inputs: inputs.into(),
+            last_inputs: last_inputs,
+        };
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return self.inputs.is_empty();
+    }
+
+    pub fn next_inputs(&mut self, board: &GameBoard) -> Inputs {
+        let inputs = self.press_next(board);
+        self.last_inputs = inputs;
+        return inputs;
+    }
+
+    fn press_next(&mut self, board: &GameBoard) -> Inputs {
+        let mut inputs = Inputs::default();
+        let input = match self.inputs.front() {
+            Some(input) => *input,
+            None => return inputs,
+        };
+
+        let held = match input {
+            MoveInput::DasLeft => Some(MoveDirection::Left),
+            MoveInput::DasRight => Some(MoveDirection::Right),
+            MoveInput::SoftDrop => Some(MoveDirection::Down),
+            _ => None,
+        };
+        if let Some(dir) = held {
+            if !board.simulate_move(dir) {
+                self.inputs.pop_front();
+                return inputs;
+            }
+            let mut held_inputs = Inputs::default();
+            match dir {
+                MoveDirection::Left => held_inputs.left = true,
+                MoveDirection::Right => held_inputs.right = true,
+                MoveDirection::Down => held_inputs.soft_drop = true,
+            }
+            // 押し始めは他のボタンを離してから
+            if self.last_inputs != Inputs::default() && self.last_inputs != held_inputs {
+                return inputs;
+            }
+            return held_inputs;
+        }
+
+        if self.last_inputs != Inputs::default() {
+            return inputs;
+        }
+        match input {
+            MoveInput::Hold => inputs.hold = true,
+            MoveInput::Left => inputs.left = true,
+            MoveInput::Right => inputs.right = true,
+            MoveInput::RotateRight => inputs.rotate_right = true,
+            MoveInput::RotateLeft => inputs.rotate_left = true,
+            MoveInput::RotateHalf => inputs.rotate_half = true,
+            MoveInput::HardDrop => inputs.hard_drop = true,
+            MoveInput::DasLeft | MoveInput::DasRight | MoveInput::SoftDrop => {}
+        }
+        self.inputs.pop_front();
+        return inputs;
+    }
+}
+
+// 固定できる置き方 1 つ
+#[derive(Clone)]
+pub struct Placement {
+    // ハードドロップした直後、固定する前の盤面
+    pub board: GameBoard,
+    pub spin: SpinType,
+    pub uses_hold: bool,
+    // ここに置くまでの一番少ない入力 最後は必ず HardDrop
+    pub inputs: Vec<MoveInput>,
+}
+
+impl Placement {
+    // ミノが埋めるマス (y, x)
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        return occupied_cells(&self.board);
+    }
+
+    // 固定してラインを消した盤面
+    pub fn lock(&self) -> (GameBoard, LockEvent) {
+        let mut board = self.board.clone();
+        board.write_tetrimono();
+        let lines = board.clear_lines();
+        let lock = LockEvent {
+            spin: self.spin,
+            lines: lines,
+            is_perfect_clear: lines > 0 && board.is_perfect_clear(),
+        };
+        return (board, lock);
+    }
+}
+
+// 置き場所と向き T だけは直前の回転も T-spin の判定に使うので区別する
+type SearchKey = ((usize, usize), usize, Option<(RotateDirection, usize)>);
+
+fn search_key(board: &GameBoard) -> Option<SearchKey> {
+    let tetrimono = board.tetrimono?;
+    let last_rotation = match tetrimono.typ {
+        TetrominoType::T => board.last_rotation,
+        _ => None,
+    };
+    return Some((board.cursor, tetrimono.state(), last_rotation));
+}
+
+// 操作中のミノと、use_hold ならホールドして出てくるミノの置き方を全部集める
+// 入力はゲームと同じように毎フレーム進めて試すので、落下速度や固定までの猶予、
+// 移動でリセットできる回数、DAS の設定も考えた上でたどり着ける置き方だけが残る
+// 埋めるマスと T-spin が同じ置き方は、入力の一番少ないものだけを残す
+pub fn find_placements(game: &Game, use_hold: bool) -> Vec<Placement> {
+    let mut placements = Vec::new();
+    let mut found = HashSet::new();
+    if game.is_over() || game.board.tetrimono.is_none() {
+        return placements;
+    }
+
+    // ボタンを押したままなら離したところから探す (InputPlayer も最初に離す)
+    let mut game = game.clone();
+    // 探している間は使わないので、状態ごとに複製しないように空にしておく
+    game.pending_garbage.clear();
+    game.splits.clear();
+    if game.last_inputs() != Inputs::default() && !play(&mut game, &[]) {
+        return placements;
+    }
+
+    search(&game, false, &mut found, &mut placements);
+    if use_hold && !game.stock.is_used && play(&mut game, &[MoveInput::Hold]) {
+        search(&game, true, &mut found, &mut placements);
+    }
+    return placements;
+}
+
+// board に typ が出てきたときの置き方 落下速度などのルールは game のものを使う
+pub fn find_spawn_placements(game: &Game, board: &GameBoard, typ: TetrominoType) -> Vec<Placement> {
+    let mut game = game.clone();
+    game.board = board.clone();
+    game.board.init_tetrimono(typ);
+    game.counter.reset();
+    game.lock_counter.init(game.board.cursor.0);
+    game.release_inputs();
+    return find_placements(&game, false);
+}
+
+// 入力を最後まで押して離す 途中で固定されたら false
+// 探している間に時間切れやせり上がりが起きないように、ミノだけを進める
+fn play(game: &mut Game, inputs: &[MoveInput]) -> bool {
+    let mut player = InputPlayer::new(inputs.to_vec(), game.last_inputs());
+    while !player.is_finished() || game.last_inputs() != Inputs::default() {
+        let inputs = player.next_inputs(&game.board);
+        let is_locked = game
+            .step_piece(inputs)
+            .iter()
+            .any(|event| matches!(event, GameEvent::Lock(_) | GameEvent::GameOver(_)));
+        if is_locked {
+            return false;
+        }
+    }
+    return true;
+}
+
+// 今の状態から入力を幅優先で試して、たどり着いた状態ごとにハードドロップした置き方を残す
+// 幅優先なので最初に見つかった置き方の入力が一番少ない
+// 同じ位置と向きには最初に着いたときの猶予の残りで続きを探す
+fn search(
+    start: &Game,
+    uses_hold: bool,
+    found: &mut HashSet<(Vec<(usize, usize)>, SpinType)>,
+    placements: &mut Vec<Placement>,
+) {
+    let start_key = match search_key(&start.board) {
+        Some(key) => key,
+        None => return,
+    };
+    let mut nodes: Vec<(Game, Option<(usize, MoveInput)>)> = vec![(start.clone(), None)];
+    let mut visited: HashMap<SearchKey, usize> = HashMap::new();
+    visited.insert(start_key, 0);
+    let mut queue = VecDeque::from([0]);
+
+    while let Some(index) = queue.pop_front() {
+        // ハードドロップは押したフレームで底まで落ちて固定される
+        let mut dropped = nodes[index].0.board.clone();
+        while dropped.simulate_move(MoveDirection::Down) {
+            dropped.apply_move(MoveDirection::Down);
+        }
+        let spin = dropped.detect_spin();
+        if found.insert((occupied_cells(&dropped), spin)) {
+            let mut inputs = vec![MoveInput::HardDrop];
+            let mut parent = nodes[index].1;
+            while let Some((from, input)) = parent {
+                inputs.push(input);
+                parent = nodes[from].1;
+            }
+            if uses_hold {
+                inputs.push(MoveInput::Hold);
+            }
+            inputs.reverse();
+            placements.push(Placement {
+                board: dropped,
+                spin: spin,
+                uses_hold: uses_hold,
+                inputs: inputs,
+            });
+        }
+
+        for input in SEARCH_INPUTS {
+            let mut next = nodes[index].0.clone();
+            if !play(&mut next, &[input]) {
+                continue;
+            }
+            let key = match search_key(&next.board) {
+                Some(key) if !visited.contains_key(&key) => key,
+                _ => continue,
+            };
+            visited.insert(key, nodes.len());
+            queue.push_back(nodes.len());
+            nodes.push((next, Some((index, input))));
+        }
+    }
+}
+
+fn occupied_cells(board: &GameBoard) -> Vec<(usize, usize)> {
+    let mut cells = Vec::new();
+    if let Some(tetrimono) = board.tetrimono {
+        for y in 0..4 {
+            for x in 0..4 {
+                if tetrimono.figure[y][x] != 0 {
+                    cells.push((board.cursor.0 + y, board.cursor.1 + x));
+                }
+            }
+        }
+    }
+    return cells;
+}
+
+#[cfg(test)]
+fn new_test_game(start_level: i64) -> Game {
+    use super::config::GameSettings;
+
+    return Game::new(GameSettings {
+        seed: Some(1),
+        start_level: start_level,
+        ..Default::default()
+    });
+}
+
+// 入力をゲームに渡して、固定されたときの盤面を返す
+#[cfg(test)]
+fn play_to_lock(game: &Game, inputs: &[MoveInput]) -> Option<GameBoard> {
+    let mut game = game.clone();
+    let mut player = InputPlayer::new(inputs.to_vec(), game.last_inputs());
+    for _ in 0..600 {
+        let mut board = game.board.clone();
+        let events = game.step(player.next_inputs(&game.board));
+        if events
+            .iter()
+            .any(|event| matches!(event, GameEvent::Lock(_)))
+        {
+            // 固定した直後に次のミノが出ているので、固定する前の盤面から落とし直す
+            while board.simulate_move(MoveDirection::Down) {
+                board.apply_move(MoveDirection::Down);
+            }
+            return Some(board);
+        }
+    }
+    return None;
+}
+
+#[test]
+fn finds_the_shortest_inputs_for_each_placement() {
+    let game = new_test_game(1);
+    let placements = find_spawn_placements(&game, &GameBoard::default(), TetrominoType::O);
+    // O ミノは 9 列に置ける
+    assert_eq!(placements.len(), 9);
+    assert_eq!(placements[0].inputs, vec![MoveInput::HardDrop]);
+
+    let left_wall = placements
+        .iter()
+        .find(|placement| placement.cells().iter().any(|(_, x)| *x == 2))
+        .unwrap();
+    assert_eq!(
+        left_wall.inputs,
+        vec![MoveInput::DasLeft, MoveInput::HardDrop]
+    );
+}
+
+#[test]
+fn finds_a_t_spin_double_under_an_overhang() {
+    let mut board = GameBoard::default();
+    for x in 2..12 {
+        if x != 3 {
+            board.board[2][x] = 8;
+        }
+        if x >= 5 {
+            board.board[3][x] = 8;
+        }
+    }
+    board.board[4][2] = 8;
+
+    let game = new_test_game(1);
+    let placements = find_spawn_placements(&game, &board, TetrominoType::T);
+    let tsd = placements
+        .iter()
+        .find(|placement| placement.spin == SpinType::Full && placement.lock().1.lines == 2)
+        .unwrap();
+    assert!(tsd.inputs.len() > 2);
+}
+
+#[test]
+fn search_does_not_run_the_mode_clock() {
+    use super::config::{GameSettings, FRAMES_PER_SECOND};
+    use super::mode::GameMode;
+
+    let expected = find_placements(&new_test_game(1), true).len();
+    // 次のフレームで時間切れになるウルトラと、次のフレームでせり上がるサバイバル
+    let modes = [
+        GameMode::Ultra { seconds: 1 },
+        GameMode::Survival {
+            seconds: 1,
+            messiness: 0,
+        },
+    ];
+    for mode in modes {
+        let mut game = Game::new(GameSettings {
+            seed: Some(1),
+            mode: mode,
+            ..Default::default()
+        });
+        game.clock.frames = FRAMES_PER_SECOND - 1;
+        assert_eq!(find_placements(&game, true).len(), expected);
+    }
+}
+
+#[test]
+fn inputs_reach_the_placement_under_gravity() {
+    // 2G と 20G で、見つけた置き方は全部ゲームに入力すると同じ場所に固定される
+    for start_level in [15, 20] {
+        let game = new_test_game(start_level);
+        let placements = find_placements(&game, true);
+        assert!(placements.len() > 10);
+        for placement in placements.iter() {
+            let locked = play_to_lock(&game, &placement.inputs).unwrap();
+            assert_eq!(occupied_cells(&locked), placement.cells());
+            assert_eq!(locked.detect_spin(), placement.spin);
+        }
+    }
+}
+
+#[test]
+fn high_gravity_cannot_climb_over_the_stack() {
+    let mut board = GameBoard::default();
+    for y in 2..6 {
+        board.board[y][9] = 8;
+    }
+    let is_right_of_stack = |placement: &Placement| placement.cells().iter().any(|(_, x)| *x > 9);
+
+    // 1G 未満なら山の上を通って右側にも置けるが、20G では底に張り付いたまま越えられない
+    let game = new_test_game(1);
+    let placements = find_spawn_placements(&game, &board, TetrominoType::O);
+    assert!(placements.iter().any(is_right_of_stack));
+
+    let game = new_test_game(20);
+    let placements = find_spawn_placements(&game, &board, TetrominoType::O);
+    assert!(!placements.is_empty());
+    assert!(!placements.iter().any(is_right_of_stack));
+}
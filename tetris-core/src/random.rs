@@ -0,0 +1,79 @@
+// 再現できるゲームのための乱数
+// PCG32 (PCG-XSH-RR 64/32, https://www.pcg-random.org/) をそのまま実装する
+// 同じシードなら rand のバージョンや環境に関係なく同じ列を返す
+
+const MULTIPLIER: u64 = 6364136223846793005;
+// ストリームは固定する (リファレンス実装のデモと同じ 54)
+const STREAM: u64 = 54;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pcg32 {
+    state: u64,
+    inc: u64,
+}
+
+impl Default for Pcg32 {
+    fn default() -> Self {
+        return Pcg32::new(0);
+    }
+}
+
+impl Pcg32 {
+    // リファレンス実装の pcg32_srandom_r(seed, 54) と同じ初期化
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Pcg32 {
+            state: 0,
+            inc: (STREAM << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        return rng;
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(self.inc);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        return xorshifted.rotate_right(rot);
+    }
+
+    // 0 以上 bound 未満を偏りなく返す (pcg32_boundedrand_r と同じ棄却法)
+    pub fn below(&mut self, bound: u32) -> u32 {
+        if bound <= 1 {
+            return 0;
+        }
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let r = self.next_u32();
+            if r >= threshold {
+                return r % bound;
+            }
+        }
+    }
+
+    // Fisher-Yates 後ろから順に入れ替える
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u32 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+// シードを指定しなかったときに使う
+pub fn random_seed() -> u64 {
+    return rand::random();
+}
+
+#[test]
+fn matches_reference_output() {
+    let mut rng = Pcg32::new(42);
+    let expected = [
+        0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e,
+    ];
+    for value in expected {
+        assert_eq!(rng.next_u32(), value);
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+
+use super::random::Pcg32;
+use super::tetrimono::TetrominoType;
+
+use self::bag::Bag;
+use self::classic::{Nes, PureRandom};
+use self::tgm::{Tgm, Tgm3};
+
+pub mod bag;
+pub mod classic;
+pub mod tgm;
+
+// ミノの出る順番の決め方
+// 乱数はキューが持っているものを使い、同じシードなら同じ順番になる
+pub trait Randomizer: Send + Sync {
+    fn next(&mut self, rng: &mut Pcg32) -> TetrominoType;
+
+    fn clone_box(&self) -> Box<dyn Randomizer>;
+}
+
+impl Clone for Box<dyn Randomizer> {
+    fn clone(&self) -> Self {
+        return self.clone_box();
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RandomizerType {
+    #[default]
+    Bag7,
+    Bag14,
+    Random,
+    Nes,
+    Tgm,
+    Tgm3,
+}
+
+impl RandomizerType {
+    pub fn create(self) -> Box<dyn Randomizer> {
+        return match self {
+            RandomizerType::Bag7 => Box::new(Bag::new(1)),
+            RandomizerType::Bag14 => Box::new(Bag::new(2)),
+            RandomizerType::Random => Box::new(PureRandom),
+            RandomizerType::Nes => Box::new(Nes::default()),
+            RandomizerType::Tgm => Box::new(Tgm::default()),
+            RandomizerType::Tgm3 => Box::new(Tgm3::default()),
+        };
+    }
+
+    pub fn name(self) -> &'static str {
+        return match self {
+            RandomizerType::Bag7 => "7-BAG",
+            RandomizerType::Bag14 => "14-BAG",
+            RandomizerType::Random => "RANDOM",
+            RandomizerType::Nes => "NES",
+            RandomizerType::Tgm => "TGM",
+            RandomizerType::Tgm3 => "TGM3",
+        };
+    }
+
+    pub fn next(self) -> Self {
+        return match self {
+            RandomizerType::Bag7 => RandomizerType::Bag14,
+            RandomizerType::Bag14 => RandomizerType::Random,
+            RandomizerType::Random => RandomizerType::Nes,
+            RandomizerType::Nes => RandomizerType::Tgm,
+            RandomizerType::Tgm => RandomizerType::Tgm3,
+            RandomizerType::Tgm3 => RandomizerType::Bag7,
+        };
+    }
+
+    pub fn prev(self) -> Self {
+        let mut typ = self;
+        while typ.next() != self {
+            typ = typ.next();
+        }
+        return typ;
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        return match name.to_lowercase().as_str() {
+            "7bag" | "bag7" | "7-bag" => Some(RandomizerType::Bag7),
+            "14bag" | "bag14" | "14-bag" => Some(RandomizerType::Bag14),
+            "random" => Some(RandomizerType::Random),
+            "nes" => Some(RandomizerType::Nes),
+            "tgm" => Some(RandomizerType::Tgm),
+            "tgm3" => Some(RandomizerType::Tgm3),
+            _ => None,
+        };
+    }
+}
+
+// from の中から等確率で 1 つ選ぶ
+fn pick(rng: &mut Pcg32, from: &[TetrominoType]) -> TetrominoType {
+    return from[rng.below(from.len() as u32) as usize];
+}
@@ -0,0 +1,36 @@
+use crate::random::Pcg32;
+use crate::tetrimono::TetrominoType;
+
+use super::Randomizer;
+
+// 7 種類を copies 個ずつ袋に入れて混ぜ、空になるまで順に出す
+#[derive(Clone)]
+pub struct Bag {
+    copies: usize,
+    bag: Vec<TetrominoType>,
+}
+
+impl Bag {
+    pub fn new(copies: usize) -> Self {
+        return Bag {
+            copies: copies.max(1),
+            bag: Vec::new(),
+        };
+    }
+}
+
+impl Randomizer for Bag {
+    fn next(&mut self, rng: &mut Pcg32) -> TetrominoType {
+        if self.bag.is_empty() {
+            for _ in 0..self.copies {
+                self.bag.extend(TetrominoType::all());
+            }
+            rng.shuffle(&mut self.bag);
+        }
+        return self.bag.remove(0);
+    }
+
+    fn clone_box(&self) -> Box<dyn Randomizer> {
+        return Box::new(self.clone());
+    }
+}
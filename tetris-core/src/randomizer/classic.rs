@@ -0,0 +1,41 @@
+use crate::random::Pcg32;
+use crate::tetrimono::TetrominoType;
+
+use super::{pick, Randomizer};
+
+// 毎回 7 種類から等確率で選ぶ
+#[derive(Clone)]
+pub struct PureRandom;
+
+impl Randomizer for PureRandom {
+    fn next(&mut self, rng: &mut Pcg32) -> TetrominoType {
+        return pick(rng, &TetrominoType::all());
+    }
+
+    fn clone_box(&self) -> Box<dyn Randomizer> {
+        return Box::new(self.clone());
+    }
+}
+
+// ファミコン版 8 面のサイコロを振り、8 か直前と同じなら 7 面で 1 回だけ振り直す
+#[derive(Clone, Default)]
+pub struct Nes {
+    last: Option<TetrominoType>,
+}
+
+impl Randomizer for Nes {
+    fn next(&mut self, rng: &mut Pcg32) -> TetrominoType {
+        let all = TetrominoType::all();
+        let roll = rng.below(all.len() as u32 + 1) as usize;
+        let typ = match all.get(roll) {
+            Some(typ) if Some(*typ) != self.last => *typ,
+            _ => pick(rng, &all),
+        };
+        self.last = Some(typ);
+        return typ;
+    }
+
+    fn clone_box(&self) -> Box<dyn Randomizer> {
+        return Box::new(self.clone());
+    }
+}
@@ -0,0 +1,185 @@
+use crate::random::Pcg32;
+use crate::tetrimono::TetrominoType;
+
+use super::{pick, Randomizer};
+
+// 最初のミノは S, Z, O にしない
+const FIRST_TYPES: [TetrominoType; 4] = [
+    TetrominoType::I,
+    TetrominoType::J,
+    TetrominoType::L,
+    TetrominoType::T,
+];
+const ROLLS: usize = 6;
+const POOL_COPIES: usize = 5;
+
+// TGM2 直近 4 個の履歴にあるミノが出たら 6 回まで振り直す
+#[derive(Clone)]
+pub struct Tgm {
+    history: [TetrominoType; 4],
+    is_first: bool,
+}
+
+impl Default for Tgm {
+    fn default() -> Self {
+        return Tgm {
+            history: [
+                TetrominoType::Z,
+                TetrominoType::S,
+                TetrominoType::S,
+                TetrominoType::Z,
+            ],
+            is_first: true,
+        };
+    }
+}
+
+impl Randomizer for Tgm {
+    fn next(&mut self, rng: &mut Pcg32) -> TetrominoType {
+        let typ = if self.is_first {
+            self.is_first = false;
+            pick(rng, &FIRST_TYPES)
+        } else {
+            let all = TetrominoType::all();
+            let mut typ = pick(rng, &all);
+            for _ in 1..ROLLS {
+                if !self.history.contains(&typ) {
+                    break;
+                }
+                typ = pick(rng, &all);
+            }
+            typ
+        };
+        push_history(&mut self.history, typ);
+        return typ;
+    }
+
+    fn clone_box(&self) -> Box<dyn Randomizer> {
+        return Box::new(self.clone());
+    }
+}
+
+// TGM3 35 個のプールから引き、引いたところには一番長く出ていないミノを入れる
+// 履歴にあるミノを引いて振り直すときもプールを書き換えて日照りを防ぐ
+#[derive(Clone)]
+pub struct Tgm3 {
+    pool: Vec<TetrominoType>,
+    history: [TetrominoType; 4],
+    // 最後に出た順 先頭が一番長く出ていない
+    order: Vec<TetrominoType>,
+    is_first: bool,
+}
+
+impl Default for Tgm3 {
+    fn default() -> Self {
+        let mut pool = Vec::new();
+        for _ in 0..POOL_COPIES {
+            pool.extend(TetrominoType::all());
+        }
+        return Tgm3 {
+            pool: pool,
+            history: [
+                TetrominoType::S,
+                TetrominoType::Z,
+                TetrominoType::S,
+                TetrominoType::Z,
+            ],
+            // 最初はどのミノも出ていないので、決まった順番で並べておく
+            order: TetrominoType::all(),
+            is_first: true,
+        };
+    }
+}
+
+impl Randomizer for Tgm3 {
+    fn next(&mut self, rng: &mut Pcg32) -> TetrominoType {
+        if self.is_first {
+            self.is_first = false;
+            let typ = pick(rng, &FIRST_TYPES);
+            self.mark_dealt(typ);
+            push_history(&mut self.history, typ);
+            return typ;
+        }
+
+        let mut index = 0;
+        let mut typ = self.pool[0];
+        for roll in 0..ROLLS {
+            index = rng.below(self.pool.len() as u32) as usize;
+            typ = self.pool[index];
+            if !self.history.contains(&typ) || roll == ROLLS - 1 {
+                break;
+            }
+            self.pool[index] = self.order[0];
+        }
+
+        self.mark_dealt(typ);
+        self.pool[index] = self.order[0];
+        push_history(&mut self.history, typ);
+        return typ;
+    }
+
+    fn clone_box(&self) -> Box<dyn Randomizer> {
+        return Box::new(self.clone());
+    }
+}
+
+impl Tgm3 {
+    // 出たミノを一番後ろに回す
+    fn mark_dealt(&mut self, typ: TetrominoType) {
+        self.order.retain(|other| *other != typ);
+        self.order.push(typ);
+    }
+}
+
+fn push_history(history: &mut [TetrominoType; 4], typ: TetrominoType) {
+    history.rotate_left(1);
+    history[3] = typ;
+}
+
+#[test]
+fn first_piece_is_never_s_z_or_o() {
+    for seed in 0..50 {
+        let mut rng = Pcg32::new(seed);
+        let typ = Tgm3::default().next(&mut rng);
+        assert!(FIRST_TYPES.contains(&typ));
+        let typ = Tgm::default().next(&mut rng);
+        assert!(FIRST_TYPES.contains(&typ));
+    }
+}
+
+#[test]
+fn tgm3_refills_the_pool_with_the_longest_absent_piece() {
+    // 履歴にない I だけのプールなら振り直さずに I が出る
+    let mut tgm3 = Tgm3 {
+        pool: vec![TetrominoType::I; POOL_COPIES * 7],
+        is_first: false,
+        ..Default::default()
+    };
+    let typ = tgm3.next(&mut Pcg32::new(1));
+    assert_eq!(typ, TetrominoType::I);
+
+    // まだ一度も出ていないミノのうち、並びの先頭の T が入る
+    let refilled: Vec<_> = tgm3
+        .pool
+        .iter()
+        .filter(|typ| **typ != TetrominoType::I)
+        .collect();
+    assert_eq!(refilled, vec![&TetrominoType::T]);
+    assert_eq!(tgm3.order.last(), Some(&TetrominoType::I));
+}
+
+#[test]
+fn tgm3_has_no_long_droughts() {
+    for seed in 0..20 {
+        let mut rng = Pcg32::new(seed);
+        let mut tgm3 = Tgm3::default();
+        let mut last_seen = [0; 8];
+        for draw in 1..=2000 {
+            let typ = tgm3.next(&mut rng);
+            last_seen[typ as usize] = draw;
+            for seen in last_seen.iter().skip(1) {
+                assert!(draw - seen <= 20, "seed {} draw {}", seed, draw);
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::error::FileError;
+use super::game::Game;
+use super::mode::GameMode;
+
+// スプリントの自己ベスト 時間はすべてフレーム数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SprintRecord {
+    pub lines: i64,
+    pub frames: i64,
+    // SPLIT_LINES ごとの経過時間
+    pub splits: Vec<i64>,
+    pub seed: u64,
+}
+
+impl SprintRecord {
+    // スプリントを最後まで消し切ったときだけ記録になる
+    pub fn from_game(game: &Game) -> Option<SprintRecord> {
+        let lines = match game.settings.mode {
+            GameMode::Sprint { lines } => lines,
+            _ => return None,
+        };
+        if !game.end.is_some_and(|end| end.is_cleared()) {
+            return None;
+        }
+        return Some(SprintRecord {
+            lines: lines,
+            frames: game.clock.frames,
+            splits: game.splits.clone(),
+            seed: game.seed,
+        });
+    }
+
+    pub fn is_better_than(&self, other: &SprintRecord) -> bool {
+        return self.frames < other.frames;
+    }
+
+    // 同じスプリットの時間の差 (マイナスなら速い)
+    pub fn split_diff(&self, index: usize, frames: i64) -> Option<i64> {
+        return self.splits.get(index).map(|best| frames - best);
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), FileError> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        return Ok(());
+    }
+
+    pub fn load(path: &Path) -> Result<SprintRecord, FileError> {
+        return Ok(serde_json::from_str(&fs::read_to_string(path)?)?);
+    }
+}
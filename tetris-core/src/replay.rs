@@ -0,0 +1,568 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::config::GameSettings;
+use super::counter::LockResetRule;
+use super::error::FileError;
+use super::event::GameEvent;
+use super::game::{Game, Inputs};
+use super::handling::Handling;
+use super::mode::GameMode;
+use super::randomizer::RandomizerType;
+use super::rotation::RotationSystemType;
+
+// バイナリ形式 (数値はすべてリトルエンディアン)
+//   "TTRP" / version: u8
+//   settings: rotation, randomizer, lock reset の名前 (u8 の長さ + ASCII)
+//             lock_delay, lock_reset_limit, das, arr, sdf, das_cut, start_level,
+//             lines_per_level (zigzag 可変長), partial_lock_out: u8, seed: u64
+//             mode の名前, mode の数値 (zigzag 可変長) ※ version 2 から
+//             せり上がりの穴の散らばり (zigzag 可変長) ※ version 3 から
+//   frames: 可変長 / 入力の数: 可変長
+//   入力: (前の入力からのフレーム差 << 1 | 押しっぱなしを捨てたか): 可変長, ボタン: u8
+const MAGIC: &[u8; 4] = b"TTRP";
+pub const REPLAY_VERSION: u8 = 3;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    File(FileError),
+    UnsupportedVersion(u8),
+    InvalidFormat(&'static str),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            ReplayError::File(error) => write!(f, "{}", error),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "unsupported replay version: {}", version)
+            }
+            ReplayError::InvalidFormat(reason) => write!(f, "invalid replay: {}", reason),
+        };
+    }
+}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(error: std::io::Error) -> Self {
+        return ReplayError::File(error.into());
+    }
+}
+
+impl From<serde_json::Error> for ReplayError {
+    fn from(error: serde_json::Error) -> Self {
+        return ReplayError::File(error.into());
+    }
+}
+
+// 入力が変わったフレームだけを記録する
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayInput {
+    pub frame: u32,
+    pub inputs: Inputs,
+    // このフレームの前に押しっぱなしの扱いを捨てた (ポーズからの再開)
+    pub is_released: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u8,
+    // seed は必ず Some
+    pub settings: GameSettings,
+    pub frames: u32,
+    pub inputs: Vec<ReplayInput>,
+}
+
+impl Replay {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let settings = &self.settings;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(REPLAY_VERSION);
+
+        write_name(&mut bytes, settings.rotation_system.name());
+        write_name(&mut bytes, settings.randomizer.name());
+        write_name(&mut bytes, settings.lock_reset_rule.name());
+        let numbers = [
+            settings.lock_delay,
+            settings.lock_reset_limit,
+            settings.handling.das,
+            settings.handling.arr,
+            settings.handling.soft_drop_factor,
+            settings.handling.das_cut,
+            settings.start_level,
+            settings.lines_per_level,
+        ];
+        for number in numbers {
+            write_varint(&mut bytes, zigzag(number));
+        }
+        bytes.push(settings.partial_lock_out as u8);
+        bytes.extend_from_slice(&settings.seed.unwrap_or(0).to_le_bytes());
+        write_name(&mut bytes, settings.mode.id());
+        write_varint(&mut bytes, zigzag(settings.mode.param()));
+        write_varint(&mut bytes, zigzag(settings.mode.messiness().unwrap_or(0)));
+
+        write_varint(&mut bytes, self.frames as u64);
+        write_varint(&mut bytes, self.inputs.len() as u64);
+        // フレーム差は負にできないので、手で並べ替えた入力でもフレーム順に書く
+        let mut inputs: Vec<&ReplayInput> = self.inputs.iter().collect();
+        inputs.sort_by_key(|input| input.frame);
+        let mut last_frame = 0;
+        for input in inputs {
+            let delta = (input.frame - last_frame) as u64;
+            write_varint(&mut bytes, delta << 1 | input.is_released as u64);
+            bytes.push(input.inputs.to_bits());
+            last_frame = input.frame;
+        }
+        return bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, ReplayError> {
+        let mut reader = Reader {
+            bytes: bytes,
+            position: 0,
+        };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(ReplayError::InvalidFormat("not a replay file"));
+        }
+        let version = reader.byte()?;
+        if !is_supported(version) {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+
+        let rotation_system = RotationSystemType::from_name(&reader.name()?)
+            .ok_or(ReplayError::InvalidFormat("unknown rotation system"))?;
+        let randomizer = RandomizerType::from_name(&reader.name()?)
+            .ok_or(ReplayError::InvalidFormat("unknown randomizer"))?;
+        let lock_reset_rule = LockResetRule::from_name(&reader.name()?)
+            .ok_or(ReplayError::InvalidFormat("unknown lock reset rule"))?;
+        let mut numbers = [0; 8];
+        for number in numbers.iter_mut() {
+            *number = unzigzag(reader.varint()?);
+        }
+        let partial_lock_out = reader.byte()? != 0;
+        let mut seed = [0; 8];
+        seed.copy_from_slice(reader.take(8)?);
+        // version 1 にはモードが無い
+        let mode = if version >= 2 {
+            let mode = GameMode::from_name(&reader.name()?)
+                .ok_or(ReplayError::InvalidFormat("unknown mode"))?;
+            mode.with_param(unzigzag(reader.varint()?))
+        } else {
+            GameMode::Endless
+        };
+        // version 2 まではせり上がりのあるモードが無い
+        let mode = if version >= 3 {
+            mode.with_messiness(unzigzag(reader.varint()?))
+        } else {
+            mode
+        };
+
+        let settings = GameSettings {
+            mode: mode,
+            rotation_system: rotation_system,
+            randomizer: randomizer,
+            lock_delay: numbers[0],
+            lock_reset_rule: lock_reset_rule,
+            lock_reset_limit: numbers[1],
+            handling: Handling {
+                das: numbers[2],
+                arr: numbers[3],
+                soft_drop_factor: numbers[4],
+                das_cut: numbers[5],
+            },
+            start_level: numbers[6],
+            lines_per_level: numbers[7],
+            partial_lock_out: partial_lock_out,
+            seed: Some(u64::from_le_bytes(seed)),
+        };
+
+        let frames = u32::try_from(reader.varint()?)
+            .map_err(|_| ReplayError::InvalidFormat("too many frames"))?;
+        let count = reader.varint()?;
+        let mut inputs = Vec::new();
+        let mut frame: u32 = 0;
+        for _ in 0..count {
+            let value = reader.varint()?;
+            // 壊れたファイルでは足すとあふれることがある
+            frame = u32::try_from(value >> 1)
+                .ok()
+                .and_then(|delta| frame.checked_add(delta))
+                .ok_or(ReplayError::InvalidFormat("frame is out of range"))?;
+            inputs.push(ReplayInput {
+                frame: frame,
+                inputs: Inputs::from_bits(reader.byte()?),
+                is_released: value & 1 == 1,
+            });
+        }
+
+        let replay = Replay {
+            version: version,
+            settings: settings,
+            frames: frames,
+            inputs: inputs,
+        };
+        return replay.validated();
+    }
+
+    // 確認用 中身をそのまま JSON にする
+    pub fn to_json(&self) -> Result<String, ReplayError> {
+        return Ok(serde_json::to_string_pretty(self)?);
+    }
+
+    pub fn from_json(text: &str) -> Result<Replay, ReplayError> {
+        let replay: Replay = serde_json::from_str(text)?;
+        if !is_supported(replay.version) {
+            return Err(ReplayError::UnsupportedVersion(replay.version));
+        }
+        return replay.validated();
+    }
+
+    // バイナリと JSON のどちらから読んでも同じように確かめて、モードの数値を収める
+    fn validated(mut self) -> Result<Replay, ReplayError> {
+        let settings = &mut self.settings;
+        settings.mode = settings.mode.clamped();
+        if settings.seed.is_none() {
+            return Err(ReplayError::InvalidFormat("seed is missing"));
+        }
+
+        let handling = settings.handling;
+        let numbers = [
+            handling.das,
+            handling.arr,
+            handling.soft_drop_factor,
+            handling.das_cut,
+        ];
+        if numbers.iter().any(|number| *number < 0) {
+            return Err(ReplayError::InvalidFormat("handling is negative"));
+        }
+
+        let is_sorted = self
+            .inputs
+            .windows(2)
+            .all(|pair| pair[0].frame <= pair[1].frame);
+        if !is_sorted {
+            return Err(ReplayError::InvalidFormat("inputs are out of order"));
+        }
+        return Ok(self);
+    }
+
+    // 拡張子が .json なら JSON、それ以外はバイナリで読み書きする
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        if is_json(path) {
+            fs::write(path, self.to_json()?)?;
+        } else {
+            fs::write(path, self.to_bytes())?;
+        }
+        return Ok(());
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, ReplayError> {
+        if is_json(path) {
+            return Replay::from_json(&fs::read_to_string(path)?);
+        }
+        return Replay::from_bytes(&fs::read(path)?);
+    }
+}
+
+// Game::step に渡した入力を毎フレーム記録する
+#[derive(Clone)]
+pub struct ReplayRecorder {
+    pub replay: Replay,
+    last: Inputs,
+    is_released: bool,
+}
+
+impl ReplayRecorder {
+    pub fn new(game: &Game) -> Self {
+        return ReplayRecorder {
+            replay: Replay {
+                version: REPLAY_VERSION,
+                settings: GameSettings {
+                    seed: Some(game.seed),
+                    ..game.settings
+                },
+                frames: 0,
+                inputs: Vec::new(),
+            },
+            last: Inputs::default(),
+            is_released: false,
+        };
+    }
+
+    // Game::step の直前に呼ぶ
+    pub fn record(&mut self, inputs: Inputs) {
+        if inputs != self.last || self.is_released {
+            self.replay.inputs.push(ReplayInput {
+                frame: self.replay.frames,
+                inputs: inputs,
+                is_released: self.is_released,
+            });
+        }
+        self.last = inputs;
+        self.is_released = false;
+        self.replay.frames += 1;
+    }
+
+    // Game::release_inputs と一緒に呼ぶ
+    pub fn release(&mut self) {
+        self.is_released = true;
+    }
+}
+
+// 記録した入力でゲームを進める
+#[derive(Clone)]
+pub struct ReplayPlayer {
+    pub replay: Replay,
+    pub frame: u32,
+    index: usize,
+    inputs: Inputs,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        return ReplayPlayer {
+            replay: replay,
+            frame: 0,
+            index: 0,
+            inputs: Inputs::default(),
+        };
+    }
+
+    // 最初のフレームに戻して新しいゲームを返す
+    pub fn start(&mut self) -> Game {
+        self.frame = 0;
+        self.index = 0;
+        self.inputs = Inputs::default();
+        return Game::new(self.replay.settings);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return self.frame >= self.replay.frames;
+    }
+
+    pub fn step(&mut self, game: &mut Game) -> Vec<GameEvent> {
+        if self.is_finished() {
+            return Vec::new();
+        }
+
+        while let Some(input) = self.replay.inputs.get(self.index) {
+            if input.frame > self.frame {
+                break;
+            }
+            if input.is_released {
+                game.release_inputs();
+            }
+            self.inputs = input.inputs;
+            self.index += 1;
+        }
+
+        self.frame += 1;
+        return game.step(self.inputs);
+    }
+
+    // 前に戻るときは最初からやり直すしかない
+    pub fn seek(&mut self, frame: u32) -> Game {
+        let mut game = self.start();
+        let frame = frame.min(self.replay.frames);
+        while self.frame < frame {
+            self.step(&mut game);
+        }
+        return game;
+    }
+}
+
+// 古い版も読めるようにしておく
+fn is_supported(version: u8) -> bool {
+    return (1..=REPLAY_VERSION).contains(&version);
+}
+
+fn is_json(path: &Path) -> bool {
+    return path
+        .extension()
+        .is_some_and(|extension| extension == "json");
+}
+
+fn write_name(bytes: &mut Vec<u8>, name: &str) {
+    bytes.push(name.len() as u8);
+    bytes.extend_from_slice(name.as_bytes());
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn zigzag(value: i64) -> u64 {
+    return ((value << 1) ^ (value >> 63)) as u64;
+}
+
+fn unzigzag(value: u64) -> i64 {
+    return (value >> 1) as i64 ^ -((value & 1) as i64);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], ReplayError> {
+        let end = self.position + length;
+        if end > self.bytes.len() {
+            return Err(ReplayError::InvalidFormat("unexpected end of file"));
+        }
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        return Ok(slice);
+    }
+
+    fn byte(&mut self) -> Result<u8, ReplayError> {
+        return Ok(self.take(1)?[0]);
+    }
+
+    fn name(&mut self) -> Result<String, ReplayError> {
+        let length = self.byte()? as usize;
+        return Ok(String::from_utf8_lossy(self.take(length)?).into_owned());
+    }
+
+    fn varint(&mut self) -> Result<u64, ReplayError> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        return Err(ReplayError::InvalidFormat("varint is too long"));
+    }
+}
+
+#[test]
+fn replay_reproduces_the_game() {
+    let settings = GameSettings {
+        seed: Some(7),
+        randomizer: RandomizerType::Tgm3,
+        mode: GameMode::Survival {
+            seconds: 2,
+            messiness: 50,
+        },
+        ..Default::default()
+    };
+    let mut game = Game::new(settings);
+    let mut recorder = ReplayRecorder::new(&game);
+
+    // 左に寄せてハードドロップ、を繰り返す
+    for frame in 0..600 {
+        let inputs = Inputs {
+            left: frame % 40 < 20,
+            rotate_right: frame % 40 == 25,
+            hard_drop: frame % 40 == 30,
+            ..Default::default()
+        };
+        if frame == 300 {
+            recorder.release();
+            game.release_inputs();
+        }
+        recorder.record(inputs);
+        game.step(inputs);
+    }
+
+    let replay = recorder.replay;
+    assert_eq!(Replay::from_bytes(&replay.to_bytes()).unwrap(), replay);
+    assert_eq!(
+        Replay::from_json(&replay.to_json().unwrap()).unwrap(),
+        replay
+    );
+
+    let mut player = ReplayPlayer::new(replay);
+    let replayed = player.seek(u32::MAX);
+    assert!(player.is_finished());
+    assert_eq!(replayed.board.board, game.board.board);
+    assert_eq!(replayed.score.score, game.score.score);
+    assert_eq!(replayed.score.pieces, game.score.pieces);
+}
+
+#[test]
+fn bad_json_mode_is_clamped() {
+    let mut replay = Replay {
+        version: REPLAY_VERSION,
+        settings: GameSettings {
+            seed: Some(1),
+            mode: GameMode::Survival {
+                seconds: 0,
+                messiness: 30,
+            },
+            ..Default::default()
+        },
+        frames: 120,
+        inputs: Vec::new(),
+    };
+    let replay_json = replay.to_json().unwrap();
+
+    let loaded = Replay::from_json(&replay_json).unwrap();
+    assert_eq!(
+        loaded.settings.mode,
+        GameMode::Survival {
+            seconds: 1,
+            messiness: 30,
+        }
+    );
+    // 0 秒ごとのせり上がりで止まらずに最後まで再生できる
+    let mut player = ReplayPlayer::new(loaded);
+    player.seek(u32::MAX);
+    assert!(player.is_finished());
+
+    replay.settings.seed = None;
+    assert!(matches!(
+        Replay::from_json(&replay.to_json().unwrap()),
+        Err(ReplayError::InvalidFormat("seed is missing"))
+    ));
+}
+
+#[test]
+fn huge_frame_delta_is_rejected() {
+    let replay = Replay {
+        version: REPLAY_VERSION,
+        settings: GameSettings {
+            seed: Some(1),
+            ..Default::default()
+        },
+        frames: 10,
+        inputs: vec![
+            ReplayInput {
+                frame: u32::MAX,
+                inputs: Inputs::default(),
+                is_released: false,
+            },
+            ReplayInput {
+                frame: 3,
+                inputs: Inputs::default(),
+                is_released: false,
+            },
+        ],
+    };
+    // 並んでいない入力も書ける
+    let mut bytes = replay.to_bytes();
+    assert!(Replay::from_bytes(&bytes).is_ok());
+
+    // 最後の入力をエンコーダで書き直した分だけ取り除き、u32 を超えるフレーム差に差し替える
+    let button = Inputs::default().to_bits();
+    let mut last = Vec::new();
+    write_varint(&mut last, ((u32::MAX - 3) as u64) << 1);
+    last.push(button);
+    assert!(bytes.ends_with(&last));
+    bytes.truncate(bytes.len() - last.len());
+    write_varint(&mut bytes, (u32::MAX as u64) << 1);
+    bytes.push(button);
+    assert!(matches!(
+        Replay::from_bytes(&bytes),
+        Err(ReplayError::InvalidFormat("frame is out of range"))
+    ));
+}
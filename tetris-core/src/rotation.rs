@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+use super::tetrimono::{Tetromino, TetrominoType};
+use super::tetris::Board;
+
+use self::ars::Ars;
+use self::nes::Nes;
+use self::srs::Srs;
+
+pub mod ars;
+pub mod nes;
+pub mod srs;
+
+// 回転法則ごとにミノの向き、出現時の向き、キックの解決方法を持つ
+// 状態は時計回りに 0, 1 (R), 2, 3 (L)
+pub trait RotationSystem: Send + Sync {
+    fn figure(&self, typ: TetrominoType, state: usize) -> [[u8; 4]; 4];
+
+    fn spawn_state(&self, _typ: TetrominoType) -> usize {
+        return 0;
+    }
+
+    // 回転後のミノに対して試す (x, y) のずらし量を順番に返す
+    fn kicks(
+        &self,
+        board: &Board,
+        tetrimono: &Tetromino,
+        cursor: (usize, usize),
+        from: usize,
+        to: usize,
+    ) -> Vec<(i64, i64)>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RotationSystemType {
+    #[default]
+    Srs,
+    Ars,
+    Nes,
+}
+
+impl RotationSystemType {
+    pub fn get(self) -> &'static dyn RotationSystem {
+        return match self {
+            RotationSystemType::Srs => &Srs,
+            RotationSystemType::Ars => &Ars,
+            RotationSystemType::Nes => &Nes,
+        };
+    }
+
+    pub fn name(self) -> &'static str {
+        return match self {
+            RotationSystemType::Srs => "SRS",
+            RotationSystemType::Ars => "ARS",
+            RotationSystemType::Nes => "NES",
+        };
+    }
+
+    pub fn next(self) -> Self {
+        return match self {
+            RotationSystemType::Srs => RotationSystemType::Ars,
+            RotationSystemType::Ars => RotationSystemType::Nes,
+            RotationSystemType::Nes => RotationSystemType::Srs,
+        };
+    }
+
+    pub fn prev(self) -> Self {
+        return self.next().next();
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        return match name.to_lowercase().as_str() {
+            "srs" => Some(RotationSystemType::Srs),
+            "ars" => Some(RotationSystemType::Ars),
+            "nes" => Some(RotationSystemType::Nes),
+            _ => None,
+        };
+    }
+}
+
+// SRS の向き (出現時の形を時計回りに回したもの) を基準に他の法則の向きを作る
+pub fn srs_figure(typ: TetrominoType, state: usize) -> [[u8; 4]; 4] {
+    let base = Tetromino::from(typ);
+    let mut figure = base.figure;
+    for _ in 0..(state % 4) {
+        figure = rotate_figure_right(figure, base.matrix_size);
+    }
+    return figure;
+}
+
+// 1 段下にずらす (y は上方向が正)
+pub fn shift_figure_down(figure: [[u8; 4]; 4]) -> [[u8; 4]; 4] {
+    let mut shifted = [[0; 4]; 4];
+    shifted[..3].copy_from_slice(&figure[1..]);
+    return shifted;
+}
+
+fn rotate_figure_right(mut figure: [[u8; 4]; 4], matrix_size: usize) -> [[u8; 4]; 4] {
+    if matrix_size < 1 {
+        return figure;
+    }
+    let x = matrix_size / 2;
+    let y = matrix_size - 1;
+
+    let mut j = 0;
+    while j < x {
+        let mut i = j;
+        while i < y - j {
+            let tmp = figure[i][j];
+            figure[i][j] = figure[j][y - i];
+            figure[j][y - i] = figure[y - i][y - j];
+            figure[y - i][y - j] = figure[y - j][i];
+            figure[y - j][i] = tmp;
+            i += 1;
+        }
+        j += 1;
+    }
+    return figure;
+}
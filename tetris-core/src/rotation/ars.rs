@@ -0,0 +1,81 @@
+use crate::config::{HEIGHT_WITH_PADDING, WIDTH_WITH_PADDING};
+use crate::tetrimono::{Tetromino, TetrominoType};
+use crate::tetris::Board;
+
+use super::{shift_figure_down, srs_figure, RotationSystem};
+
+// Arika Rotation System (TGM)
+// 横向きの状態はボックスの下に揃え、キックは右 1 マス、左 1 マスの順に試す
+// I と O はキックしない
+pub struct Ars;
+
+impl RotationSystem for Ars {
+    fn figure(&self, typ: TetrominoType, state: usize) -> [[u8; 4]; 4] {
+        return match typ {
+            TetrominoType::T | TetrominoType::L | TetrominoType::J => {
+                let srs_state = (state + 2) % 4;
+                if srs_state == 0 {
+                    shift_figure_down(srs_figure(typ, srs_state))
+                } else {
+                    srs_figure(typ, srs_state)
+                }
+            }
+            TetrominoType::S => srs_figure(typ, [2, 3][state % 2]),
+            TetrominoType::Z => srs_figure(typ, [2, 1][state % 2]),
+            TetrominoType::I => srs_figure(typ, state % 2),
+            TetrominoType::O => srs_figure(typ, 0),
+        };
+    }
+
+    fn kicks(
+        &self,
+        board: &Board,
+        tetrimono: &Tetromino,
+        cursor: (usize, usize),
+        _from: usize,
+        _to: usize,
+    ) -> Vec<(i64, i64)> {
+        match tetrimono.typ {
+            TetrominoType::I | TetrominoType::O => return vec![(0, 0)],
+            TetrominoType::T | TetrominoType::L | TetrominoType::J
+                if is_blocked_at_center_column(board, tetrimono, cursor) =>
+            {
+                return vec![(0, 0)];
+            }
+            _ => {}
+        }
+
+        return vec![(0, 0), (1, 0), (-1, 0)];
+    }
+}
+
+// 上の段から左から順に調べて、最初にぶつかったマスが中央の列ならキックしない
+fn is_blocked_at_center_column(
+    board: &Board,
+    tetrimono: &Tetromino,
+    cursor: (usize, usize),
+) -> bool {
+    for y in (0..3).rev() {
+        for x in 0..3 {
+            if tetrimono.figure[y][x] == 0 {
+                continue;
+            }
+
+            let (board_y, board_x) = (cursor.0 + y, cursor.1 + x);
+            if board_y >= HEIGHT_WITH_PADDING
+                || board_x >= WIDTH_WITH_PADDING
+                || board[board_y][board_x] != 0
+            {
+                return x == 1;
+            }
+        }
+    }
+    return false;
+}
+
+#[test]
+fn t_mino_spawns_pointing_down() {
+    let figure = Ars.figure(TetrominoType::T, 0);
+    assert_eq!(figure[1], [1, 1, 1, 0]);
+    assert_eq!(figure[0], [0, 1, 0, 0]);
+}
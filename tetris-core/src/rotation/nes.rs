@@ -0,0 +1,31 @@
+use crate::tetrimono::{Tetromino, TetrominoType};
+use crate::tetris::Board;
+
+use super::{srs_figure, RotationSystem};
+
+// Nintendo Rotation System (ファミコン版)
+// T, L, J は平らな面を上にして出現し、中心のまわりに 4 方向回る
+// S, Z, I は 2 方向のみで、キックは一切しない
+pub struct Nes;
+
+impl RotationSystem for Nes {
+    fn figure(&self, typ: TetrominoType, state: usize) -> [[u8; 4]; 4] {
+        let srs_state = match typ {
+            TetrominoType::T | TetrominoType::L | TetrominoType::J => (state + 2) % 4,
+            TetrominoType::S | TetrominoType::Z | TetrominoType::I => [2, 1][state % 2],
+            TetrominoType::O => 0,
+        };
+        return srs_figure(typ, srs_state);
+    }
+
+    fn kicks(
+        &self,
+        _board: &Board,
+        _tetrimono: &Tetromino,
+        _cursor: (usize, usize),
+        _from: usize,
+        _to: usize,
+    ) -> Vec<(i64, i64)> {
+        return vec![(0, 0)];
+    }
+}
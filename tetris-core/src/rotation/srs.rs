@@ -0,0 +1,114 @@
+use crate::tetrimono::{Tetromino, TetrominoType};
+use crate::tetris::Board;
+
+use super::{srs_figure, RotationSystem};
+
+// Super Rotation System
+// キックテーブル
+// (x, y) の順で、y は上方向が正 (board の添字と同じ向き)
+// 状態は 0 = 出現時, 1 = R, 2 = 180°, 3 = L
+
+pub const KICK_COUNT: usize = 5;
+
+type KickTable = [[(i64, i64); KICK_COUNT]; 8];
+
+// 0->R, R->0, R->2, 2->R, 2->L, L->2, L->0, 0->L
+const JLSTZ_KICKS: KickTable = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+];
+
+// 0->R, R->0, R->2, 2->R, 2->L, L->2, L->0, 0->L
+const I_KICKS: KickTable = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+];
+
+// 180° 回転は公式の SRS には無いので、最近のクライアント (TETR.IO) の表を使う
+// 0->2, 2->0, R->L, L->R
+const HALF_TURN_KICKS: [[(i64, i64); 6]; 4] = [
+    [(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)],
+    [(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)],
+    [(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],
+    [(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)],
+];
+
+// O ミノは回転しても形が変わらないのでキックしない
+const NO_KICKS: [(i64, i64); 1] = [(0, 0)];
+
+fn transition_index(from: usize, to: usize) -> Option<usize> {
+    return match (from, to) {
+        (0, 1) => Some(0),
+        (1, 0) => Some(1),
+        (1, 2) => Some(2),
+        (2, 1) => Some(3),
+        (2, 3) => Some(4),
+        (3, 2) => Some(5),
+        (3, 0) => Some(6),
+        (0, 3) => Some(7),
+        _ => None,
+    };
+}
+
+fn half_turn_index(from: usize, to: usize) -> Option<usize> {
+    return match (from, to) {
+        (0, 2) => Some(0),
+        (2, 0) => Some(1),
+        (1, 3) => Some(2),
+        (3, 1) => Some(3),
+        _ => None,
+    };
+}
+
+// matrix_size で I (4) / JLSTZ (3) / O (0) を判別する
+pub fn get_kicks(matrix_size: usize, from: usize, to: usize) -> &'static [(i64, i64)] {
+    if matrix_size < 1 {
+        return &NO_KICKS;
+    }
+
+    if let Some(index) = half_turn_index(from, to) {
+        return &HALF_TURN_KICKS[index];
+    }
+
+    let index = match transition_index(from, to) {
+        Some(index) => index,
+        None => return &NO_KICKS,
+    };
+
+    return match matrix_size {
+        4 => &I_KICKS[index],
+        3 => &JLSTZ_KICKS[index],
+        _ => &NO_KICKS,
+    };
+}
+
+pub struct Srs;
+
+impl RotationSystem for Srs {
+    fn figure(&self, typ: TetrominoType, state: usize) -> [[u8; 4]; 4] {
+        return srs_figure(typ, state);
+    }
+
+    fn kicks(
+        &self,
+        _board: &Board,
+        tetrimono: &Tetromino,
+        _cursor: (usize, usize),
+        from: usize,
+        to: usize,
+    ) -> Vec<(i64, i64)> {
+        return get_kicks(tetrimono.matrix_size, from, to).to_vec();
+    }
+}
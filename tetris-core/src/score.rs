@@ -0,0 +1,182 @@
+use super::config::LINES_PER_LEVEL;
+use super::event::{ScoreEvent, ScoreKind};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SpinType {
+    #[default]
+    None,
+    Mini,
+    Full,
+}
+
+// ガイドラインの得点 (レベル 1 あたり)
+// 添字は消したライン数
+const LINE_CLEAR_POINTS: [i64; 5] = [0, 100, 300, 500, 800];
+const MINI_SPIN_POINTS: [i64; 5] = [100, 200, 400, 0, 0];
+const SPIN_POINTS: [i64; 5] = [400, 800, 1200, 1600, 0];
+const PERFECT_CLEAR_POINTS: [i64; 5] = [0, 800, 1200, 1800, 2000];
+const B2B_TETRIS_PERFECT_CLEAR_POINTS: i64 = 3200;
+const COMBO_POINTS: i64 = 50;
+const SOFT_DROP_POINTS: i64 = 1;
+const HARD_DROP_POINTS: i64 = 2;
+
+#[derive(Clone)]
+pub struct Score {
+    pub score: i64,
+    pub level: i64,
+    pub lines: i64,
+    pub pieces: i64,
+    // 連続でラインを消した回数 消していないときは -1
+    pub combo: i64,
+    // 直前のライン消去がテトリスか T-spin だったか
+    pub back_to_back: bool,
+    pub start_level: i64,
+    pub lines_per_level: i64,
+    // これ以上レベルを上げない (None なら上限なし)
+    pub max_level: Option<i64>,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        return Score::new(1, LINES_PER_LEVEL);
+    }
+}
+
+impl Score {
+    pub fn new(start_level: i64, lines_per_level: i64) -> Self {
+        return Score {
+            score: 0,
+            level: start_level.max(1),
+            lines: 0,
+            pieces: 0,
+            combo: -1,
+            back_to_back: false,
+            start_level: start_level.max(1),
+            lines_per_level: lines_per_level.max(1),
+            max_level: None,
+        };
+    }
+
+    pub fn on_drop(&mut self, cells: i64, is_hard: bool) -> ScoreEvent {
+        let (kind, points) = if is_hard {
+            (
+                ScoreKind::HardDrop { cells: cells },
+                cells * HARD_DROP_POINTS,
+            )
+        } else {
+            (
+                ScoreKind::SoftDrop { cells: cells },
+                cells * SOFT_DROP_POINTS,
+            )
+        };
+        return self.add(kind, points);
+    }
+
+    // ミノを置いてラインを消した後に呼ぶ
+    pub fn on_lock(
+        &mut self,
+        lines: u8,
+        spin: SpinType,
+        is_perfect_clear: bool,
+    ) -> Vec<ScoreEvent> {
+        let mut events = Vec::new();
+        let index = (lines as usize).min(4);
+        self.pieces += 1;
+
+        if lines == 0 {
+            self.combo = -1;
+            if spin != SpinType::None {
+                let points = self.spin_points(spin, index) * self.level;
+                events.push(self.add(
+                    ScoreKind::LineClear {
+                        lines: lines,
+                        spin: spin,
+                        back_to_back: false,
+                    },
+                    points,
+                ));
+            }
+            return events;
+        }
+
+        // テトリスとラインを消した T-spin は Back-to-Back の対象
+        let is_difficult = lines >= 4 || spin != SpinType::None;
+        let back_to_back = is_difficult && self.back_to_back;
+        self.back_to_back = is_difficult;
+
+        let mut points = self.spin_points(spin, index) * self.level;
+        if back_to_back {
+            points = points * 3 / 2;
+        }
+        events.push(self.add(
+            ScoreKind::LineClear {
+                lines: lines,
+                spin: spin,
+                back_to_back: back_to_back,
+            },
+            points,
+        ));
+
+        self.combo += 1;
+        if self.combo > 0 {
+            let points = COMBO_POINTS * self.combo * self.level;
+            events.push(self.add(ScoreKind::Combo { count: self.combo }, points));
+        }
+
+        if is_perfect_clear {
+            let points = if back_to_back && lines >= 4 {
+                B2B_TETRIS_PERFECT_CLEAR_POINTS
+            } else {
+                PERFECT_CLEAR_POINTS[index]
+            };
+            events.push(self.add(
+                ScoreKind::PerfectClear { lines: lines },
+                points * self.level,
+            ));
+        }
+
+        self.lines += lines as i64;
+        self.level = self.start_level + self.lines / self.lines_per_level;
+        if let Some(max_level) = self.max_level {
+            self.level = self.level.min(max_level.max(self.start_level));
+        }
+        return events;
+    }
+
+    fn spin_points(&self, spin: SpinType, index: usize) -> i64 {
+        return match spin {
+            SpinType::None => LINE_CLEAR_POINTS[index],
+            SpinType::Mini => MINI_SPIN_POINTS[index],
+            SpinType::Full => SPIN_POINTS[index],
+        };
+    }
+
+    fn add(&mut self, kind: ScoreKind, points: i64) -> ScoreEvent {
+        self.score += points;
+        return ScoreEvent {
+            kind: kind,
+            points: points,
+        };
+    }
+}
+
+#[test]
+fn back_to_back_tetris_with_combo() {
+    let mut score = Score::default();
+
+    score.on_lock(4, SpinType::None, false);
+    assert_eq!(score.score, 800);
+
+    let events = score.on_lock(4, SpinType::None, false);
+    assert_eq!(events[0].points, 1200);
+    assert_eq!(events[1].kind, ScoreKind::Combo { count: 1 });
+    assert_eq!(score.score, 800 + 1200 + 50);
+
+    // 普通のシングルで Back-to-Back は途切れる
+    score.on_lock(1, SpinType::None, false);
+    assert!(!score.back_to_back);
+    assert_eq!(score.lines, 9);
+
+    score.on_lock(1, SpinType::None, false);
+    assert_eq!(score.level, 2);
+}
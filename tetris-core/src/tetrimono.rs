@@ -1,13 +1,16 @@
 use super::config::TETRIMONO_MATRIX_SIZE;
+use super::rotation::RotationSystem;
+use super::tetris::RotateDirection;
 
 #[derive(Clone, Copy)]
 pub struct Tetromino {
+    pub typ: TetrominoType,
     pub figure: [[u8; 4]; 4],
     pub matrix_size: usize,
     pub rotation: i64,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TetrominoType {
     T = 1,
     L = 2,
@@ -19,10 +22,6 @@ pub enum TetrominoType {
 }
 
 impl TetrominoType {
-    pub fn get_tetromono(self) -> Tetromino {
-        return Tetromino::from(self);
-    }
-
     pub fn all() -> Vec<TetrominoType> {
         return Vec::from([
             TetrominoType::T,
@@ -37,8 +36,9 @@ impl TetrominoType {
 }
 
 impl Tetromino {
-    fn new(figure: [[u8; 4]; 4], matrix_size: usize) -> Self {
+    fn new(typ: TetrominoType, figure: [[u8; 4]; 4], matrix_size: usize) -> Self {
         return Tetromino {
+            typ: typ,
             figure: figure,
             matrix_size: matrix_size,
             rotation: 0,
@@ -55,7 +55,7 @@ impl Tetromino {
         figure[1][1] = 1;
         figure[1][2] = 1;
         figure[2][1] = 1;
-        return Tetromino::new(figure, 3);
+        return Tetromino::new(TetrominoType::T, figure, 3);
     }
 
     // 0,0,0,0
@@ -68,7 +68,7 @@ impl Tetromino {
         figure[1][1] = 2;
         figure[1][2] = 2;
         figure[2][2] = 2;
-        return Tetromino::new(figure, 3);
+        return Tetromino::new(TetrominoType::L, figure, 3);
     }
 
     // 0,0,0,0
@@ -81,7 +81,7 @@ impl Tetromino {
         figure[1][1] = 3;
         figure[1][2] = 3;
         figure[2][0] = 3;
-        return Tetromino::new(figure, 3);
+        return Tetromino::new(TetrominoType::J, figure, 3);
     }
 
     // 0,0,0,0
@@ -94,7 +94,7 @@ impl Tetromino {
         figure[0][1] = 4;
         figure[1][0] = 4;
         figure[1][1] = 4;
-        return Tetromino::new(figure, 0);
+        return Tetromino::new(TetrominoType::O, figure, 0);
     }
 
     // 0,0,0,0
@@ -107,7 +107,7 @@ impl Tetromino {
         figure[1][1] = 5;
         figure[2][1] = 5;
         figure[2][2] = 5;
-        return Tetromino::new(figure, 3);
+        return Tetromino::new(TetrominoType::S, figure, 3);
     }
 
     // 0,0,0,0
@@ -120,20 +120,20 @@ impl Tetromino {
         figure[1][2] = 6;
         figure[2][0] = 6;
         figure[2][1] = 6;
-        return Tetromino::new(figure, 3);
+        return Tetromino::new(TetrominoType::Z, figure, 3);
     }
 
-    // 0,0,0,0
     // 0,0,0,0
     // 1,1,1,1
     // 0,0,0,0
+    // 0,0,0,0
     fn type_i() -> Self {
         let mut figure = [[0; TETRIMONO_MATRIX_SIZE]; TETRIMONO_MATRIX_SIZE];
-        figure[1][0] = 7;
-        figure[1][1] = 7;
-        figure[1][2] = 7;
-        figure[1][3] = 7;
-        return Tetromino::new(figure, 4);
+        figure[2][0] = 7;
+        figure[2][1] = 7;
+        figure[2][2] = 7;
+        figure[2][3] = 7;
+        return Tetromino::new(TetrominoType::I, figure, 4);
     }
 
     pub fn from(typ: TetrominoType) -> Self {
@@ -148,50 +148,25 @@ impl Tetromino {
         }
     }
 
-    pub fn rotate_right(&mut self) {
-        if self.matrix_size < 1 {
-            return;
-        }
-        let x = self.matrix_size / 2;
-        let y = self.matrix_size - 1;
-
-        let mut j = 0;
-        while j < x {
-            let mut i = j;
-            while i < y - j {
-                let tmp = self.figure[i][j];
-                self.figure[i][j] = self.figure[j][y - i];
-                self.figure[j][y - i] = self.figure[y - i][y - j];
-                self.figure[y - i][y - j] = self.figure[y - j][i];
-                self.figure[y - j][i] = tmp;
-                i += 1;
-            }
-            j += 1;
-        }
-        self.rotation += 90;
+    // 0 = 出現時, 1 = R, 2 = 180°, 3 = L
+    pub fn state(&self) -> usize {
+        return (self.rotation.rem_euclid(360) / 90) as usize;
     }
 
-    // TODO まだ
-    pub fn rotate_left(&mut self) {
-        if self.matrix_size < 1 {
-            return;
-        }
-        let x = self.matrix_size / 2;
-        let y = self.matrix_size - 1;
+    // 回転法則の出現時の向きで作る
+    pub fn spawn(typ: TetrominoType, system: &dyn RotationSystem) -> Self {
+        let mut tetrimono = Tetromino::from(typ);
+        tetrimono.rotation = system.spawn_state(typ) as i64 * 90;
+        tetrimono.figure = system.figure(typ, tetrimono.state());
+        return tetrimono;
+    }
 
-        let mut j = 0;
-        while j < x {
-            let mut i = j;
-            while i < y - j {
-                let tmp = self.figure[i][j];
-                self.figure[i][j] = self.figure[j][y - i];
-                self.figure[j][y - i] = self.figure[y - i][y - j];
-                self.figure[y - i][y - j] = self.figure[y - j][i];
-                self.figure[y - j][i] = tmp;
-                i += 1;
-            }
-            j += 1;
-        }
-        self.rotation -= 90;
+    pub fn rotate(&mut self, system: &dyn RotationSystem, dir: RotateDirection) {
+        self.rotation += match dir {
+            RotateDirection::Right => 90,
+            RotateDirection::Left => -90,
+            RotateDirection::Half => 180,
+        };
+        self.figure = system.figure(self.typ, self.state());
     }
 }
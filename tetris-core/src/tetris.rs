@@ -0,0 +1,703 @@
+use super::config::{
+    GARBAGE_CELL, HEIGHT_PADDING, HEIGHT_WITH_PADDING, INITIAL_MONO_POSITION, MAX_HEIGHT,
+    MAX_WIDTH, MIN_QUEUE_MONO_COUNT, VISIBLE_HEIGHT, WIDTH_WITH_PADDING,
+};
+use super::random::Pcg32;
+use super::randomizer::{Randomizer, RandomizerType};
+use super::rotation::RotationSystemType;
+use super::score::SpinType;
+use super::tetrimono::{Tetromino, TetrominoType};
+
+pub type Board = [[u8; WIDTH_WITH_PADDING]; HEIGHT_WITH_PADDING];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveDirection {
+    Down,
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RotateDirection {
+    Right,
+    Left,
+    Half,
+}
+
+// ガイドラインのゲームオーバー条件 (名前はガイドラインの用語に合わせる)
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopOut {
+    // 置いたミノが全部見えない段にある
+    LockOut,
+    // 置いたミノが一部でも見えない段にある (設定で有効にしたときだけ)
+    PartialLockOut,
+    // 出てきたミノが既にあるブロックと重なった
+    BlockOut,
+    // せり上がりでブロックかミノがバッファより上に押し出された
+    PushOut,
+}
+
+impl TopOut {
+    pub fn name(self) -> &'static str {
+        return match self {
+            TopOut::LockOut => "LOCK OUT",
+            TopOut::PartialLockOut => "PARTIAL LOCK OUT",
+            TopOut::BlockOut => "BLOCK OUT",
+            TopOut::PushOut => "PUSH OUT",
+        };
+    }
+}
+
+#[derive(Clone)]
+pub struct GameBoard {
+    pub board: Board,
+    pub cursor: (usize, usize),
+    pub tetrimono: Option<Tetromino>,
+    pub rotation_system: RotationSystemType,
+    // 最後に成功した操作が回転なら、その向きと使ったキックの番号
+    pub last_rotation: Option<(RotateDirection, usize)>,
+}
+
+#[derive(Clone)]
+pub struct TetrimonoQueue {
+    pub queue: Vec<TetrominoType>,
+    pub rng: Pcg32,
+    pub randomizer: Box<dyn Randomizer>,
+}
+
+#[derive(Clone, Default)]
+pub struct StockTetrimono {
+    pub mono: Option<Tetromino>,
+    // ミノを置くまでホールドは 1 回だけ
+    pub is_used: bool,
+}
+
+impl Default for GameBoard {
+    fn default() -> Self {
+        let mut board = [[0; WIDTH_WITH_PADDING]; HEIGHT_WITH_PADDING];
+        for h in 0..(HEIGHT_WITH_PADDING) {
+            board[h][0] = 9;
+            board[h][1] = 9;
+            board[h][WIDTH_WITH_PADDING - 1] = 9;
+            board[h][WIDTH_WITH_PADDING - 2] = 9;
+        }
+
+        for w in 0..(WIDTH_WITH_PADDING) {
+            board[0][w] = 9;
+            board[1][w] = 9;
+        }
+        return GameBoard {
+            board: board,
+            cursor: (0, 0),
+            tetrimono: None,
+            rotation_system: RotationSystemType::default(),
+            last_rotation: None,
+        };
+    }
+}
+
+impl GameBoard {
+    pub fn get_written_clone(&self) -> Board {
+        if self.tetrimono.is_none() {
+            return self.board;
+        }
+
+        let mut board_clone = self.board;
+        let tetrimono = self.tetrimono.unwrap();
+        for y in 0..4 {
+            for x in 0..4 {
+                if tetrimono.figure[y][x] == 0 {
+                    continue;
+                }
+                board_clone[self.cursor.0 + y][self.cursor.1 + x] = tetrimono.figure[y][x];
+            }
+        }
+        return board_clone;
+    }
+
+    // ハードドロップしたときに止まる位置
+    pub fn get_ghost_cursor(&self) -> Option<(usize, usize)> {
+        let tetrimono = self.tetrimono?;
+        let mut cursor = self.cursor;
+        while let Some(next) = GameBoard::offset_cursor(cursor, 0, -1) {
+            if GameBoard::check_duplicate(self.board, tetrimono, next) {
+                break;
+            }
+            cursor = next;
+        }
+        return Some(cursor);
+    }
+
+    pub fn write_tetrimono(&mut self) {
+        if self.tetrimono.is_none() {
+            return;
+        }
+
+        let tetrimono = self.tetrimono.unwrap();
+        for y in 0..4 {
+            for x in 0..4 {
+                if tetrimono.figure[y][x] == 0 {
+                    continue;
+                }
+                self.board[self.cursor.0 + y][self.cursor.1 + x] = tetrimono.figure[y][x];
+            }
+        }
+        self.tetrimono = None;
+    }
+
+    pub fn init_tetrimono(&mut self, typ: TetrominoType) {
+        let tetrimono = Tetromino::spawn(typ, self.rotation_system.get());
+        self.cursor = INITIAL_MONO_POSITION;
+        if tetrimono.matrix_size == 4 {
+            // I ミノは横一列を他のミノの平らな面と同じ高さに揃える
+            self.cursor.0 -= 1;
+        }
+        self.tetrimono = Some(tetrimono);
+        self.last_rotation = None;
+    }
+    pub fn simulate_move(&self, dir: MoveDirection) -> bool {
+        if self.tetrimono.is_none() {
+            return false;
+        }
+
+        let tetrimono = self.tetrimono.unwrap();
+        let offset = match dir {
+            MoveDirection::Down => (0, -1),
+            MoveDirection::Left => (-1, 0),
+            MoveDirection::Right => (1, 0),
+        };
+
+        // I ミノを縦にして壁に寄せると cursor が 0 になるので符号付きで計算する
+        let cursor = match GameBoard::offset_cursor(self.cursor, offset.0, offset.1) {
+            Some(cursor) => cursor,
+            None => return false,
+        };
+
+        return !GameBoard::check_duplicate(self.board, tetrimono, cursor);
+    }
+
+    pub fn apply_move(&mut self, dir: MoveDirection) {
+        self.cursor = match dir {
+            MoveDirection::Down => (self.cursor.0 - 1, self.cursor.1),
+            MoveDirection::Left => (self.cursor.0, self.cursor.1 - 1),
+            MoveDirection::Right => (self.cursor.0, self.cursor.1 + 1),
+        };
+        self.last_rotation = None;
+    }
+
+    pub fn try_rotate(&mut self, dir: RotateDirection) -> bool {
+        if self.tetrimono.is_none() {
+            return false;
+        }
+
+        let system = self.rotation_system.get();
+        let mut tetrimono = self.tetrimono.unwrap();
+        let from = tetrimono.state();
+        tetrimono.rotate(system, dir);
+        let to = tetrimono.state();
+
+        // 回転法則のキックを順番に試して最初に置ける位置を採用する
+        let kicks = system.kicks(&self.board, &tetrimono, self.cursor, from, to);
+        for (i, (dx, dy)) in kicks.into_iter().enumerate() {
+            let cursor = match GameBoard::offset_cursor(self.cursor, dx, dy) {
+                Some(cursor) => cursor,
+                None => continue,
+            };
+
+            if !GameBoard::check_duplicate(self.board, tetrimono, cursor) {
+                self.tetrimono = Some(tetrimono);
+                self.cursor = cursor;
+                self.last_rotation = Some((dir, i));
+                return true;
+            }
+        }
+
+        return false;
+    }
+
+    fn offset_cursor(cursor: (usize, usize), dx: i64, dy: i64) -> Option<(usize, usize)> {
+        let y = cursor.0.checked_add_signed(dy as isize)?;
+        let x = cursor.1.checked_add_signed(dx as isize)?;
+        return Some((y, x));
+    }
+
+    fn check_duplicate(board: Board, tetrimono: Tetromino, cursor: (usize, usize)) -> bool {
+        for y in 0..4 {
+            for x in 0..4 {
+                if tetrimono.figure[y][x] == 0 {
+                    continue;
+                }
+                if cursor.0 + y >= HEIGHT_WITH_PADDING || cursor.1 + x >= WIDTH_WITH_PADDING {
+                    return true;
+                }
+                if tetrimono.figure[y][x] * board[cursor.0 + y][cursor.1 + x] != 0 {
+                    return true;
+                }
+            }
+        }
+        return false;
+    }
+
+    // 3 コーナールールで T-spin を判定する 固定する前に呼ぶ
+    pub fn detect_spin(&self) -> SpinType {
+        let tetrimono = match self.tetrimono {
+            Some(tetrimono) => tetrimono,
+            None => return SpinType::None,
+        };
+        let (dir, kick) = match self.last_rotation {
+            Some(last_rotation) => last_rotation,
+            None => return SpinType::None,
+        };
+        if tetrimono.typ != TetrominoType::T {
+            return SpinType::None;
+        }
+
+        // 回転法則によって T の位置が違うので、形から中心と向きを求める
+        let (center, front) = match GameBoard::find_t_center(&tetrimono) {
+            Some(found) => found,
+            None => return SpinType::None,
+        };
+
+        let y = (self.cursor.0 + center.1) as i64;
+        let x = (self.cursor.1 + center.0) as i64;
+        let mut corner_count = 0;
+        let mut front_count = 0;
+        for (dx, dy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            if !self.is_blocked(y + dy, x + dx) {
+                continue;
+            }
+            corner_count += 1;
+            if dx == front.0 || dy == front.1 {
+                front_count += 1;
+            }
+        }
+
+        if corner_count < 3 {
+            return SpinType::None;
+        }
+
+        // 前の 2 つが埋まっているか、SRS の 5 番目のキックを使ったなら Mini ではない
+        if front_count == 2 || (dir != RotateDirection::Half && kick == 4) {
+            return SpinType::Full;
+        }
+        return SpinType::Mini;
+    }
+
+    // T の中心のマス (x, y) と、とがっている向き (dx, dy)
+    fn find_t_center(tetrimono: &Tetromino) -> Option<((usize, usize), (i64, i64))> {
+        let filled = |x: i64, y: i64| -> bool {
+            if !(0..4).contains(&x) || !(0..4).contains(&y) {
+                return false;
+            }
+            return tetrimono.figure[y as usize][x as usize] != 0;
+        };
+
+        for y in 0..4 {
+            for x in 0..4 {
+                if !filled(x, y) {
+                    continue;
+                }
+
+                let sides = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+                let missing: Vec<&(i64, i64)> = sides
+                    .iter()
+                    .filter(|(dx, dy)| !filled(x + dx, y + dy))
+                    .collect();
+                if missing.len() == 1 {
+                    let front = (-missing[0].0, -missing[0].1);
+                    return Some(((x as usize, y as usize), front));
+                }
+            }
+        }
+        return None;
+    }
+
+    fn is_blocked(&self, y: i64, x: i64) -> bool {
+        if y < 0 || x < 0 || y >= HEIGHT_WITH_PADDING as i64 || x >= WIDTH_WITH_PADDING as i64 {
+            return true;
+        }
+        return self.board[y as usize][x as usize] != 0;
+    }
+
+    // 固定する直前に呼ぶ
+    pub fn check_lock_out(&self, is_partial: bool) -> Option<TopOut> {
+        let tetrimono = self.tetrimono?;
+        let skyline = HEIGHT_PADDING / 2 + VISIBLE_HEIGHT;
+
+        let mut blocks = 0;
+        let mut hidden_blocks = 0;
+        for y in 0..4 {
+            for x in 0..4 {
+                if tetrimono.figure[y][x] == 0 {
+                    continue;
+                }
+                blocks += 1;
+                if self.cursor.0 + y >= skyline {
+                    hidden_blocks += 1;
+                }
+            }
+        }
+
+        if hidden_blocks == blocks {
+            return Some(TopOut::LockOut);
+        }
+        if is_partial && hidden_blocks > 0 {
+            return Some(TopOut::PartialLockOut);
+        }
+        return None;
+    }
+
+    // ライン消去の後、次のミノを出してから呼ぶ
+    pub fn check_block_out(&self) -> Option<TopOut> {
+        let tetrimono = match self.tetrimono {
+            Some(tetrimono) => tetrimono,
+            None => return Some(TopOut::BlockOut),
+        };
+
+        if GameBoard::check_duplicate(self.board, tetrimono, self.cursor) {
+            return Some(TopOut::BlockOut);
+        }
+        return None;
+    }
+
+    pub fn is_perfect_clear(&self) -> bool {
+        let start_pos = HEIGHT_PADDING / 2;
+        for y in start_pos..(start_pos + MAX_HEIGHT) {
+            for x in start_pos..(start_pos + MAX_WIDTH) {
+                if self.board[y][x] != 0 {
+                    return false;
+                }
+            }
+        }
+        return true;
+    }
+
+    // 以下雑
+    pub fn clear_lines(&mut self) -> u8 {
+        let start_pos = HEIGHT_PADDING / 2;
+        let mut clear_line_count = 0;
+
+        let mut y = start_pos;
+        while y < start_pos + MAX_HEIGHT {
+            if !self.clear_line(y) {
+                y += 1;
+                continue;
+            }
+
+            self.shift_lines(y); // TODO スゴクムダ？
+            clear_line_count += 1;
+        }
+
+        return clear_line_count;
+    }
+
+    fn clear_line(&mut self, y: usize) -> bool {
+        let start_pos = HEIGHT_PADDING / 2;
+        for x in start_pos..(start_pos + MAX_WIDTH) {
+            if self.board[y][x] == 0 || self.board[y][x] == 9 {
+                return false;
+            }
+        }
+
+        for x in start_pos..(start_pos + MAX_WIDTH) {
+            self.board[y][x] = 0;
+        }
+        return true;
+    }
+
+    // 一番下にせり上がりの段を 1 段入れて、上の段をすべて 1 段押し上げる (shift_lines の逆)
+    // hole は穴を空ける列 (0 から MAX_WIDTH - 1)
+    pub fn insert_garbage_line(&mut self, hole: usize) -> Option<TopOut> {
+        let start_pos = HEIGHT_PADDING / 2;
+        let columns = start_pos..(start_pos + MAX_WIDTH);
+
+        for y in ((start_pos + 1)..HEIGHT_WITH_PADDING).rev() {
+            for x in columns.clone() {
+                self.board[y][x] = self.board[y - 1][x];
+            }
+        }
+        for x in columns.clone() {
+            self.board[start_pos][x] = if x - start_pos == hole {
+                0
+            } else {
+                GARBAGE_CELL
+            };
+        }
+
+        // 操作中のミノに重なったら重ならなくなるまで上に逃がす
+        if let Some(tetrimono) = self.tetrimono {
+            while GameBoard::check_duplicate(self.board, tetrimono, self.cursor) {
+                if self.cursor.0 + 1 >= HEIGHT_WITH_PADDING {
+                    return Some(TopOut::PushOut);
+                }
+                self.cursor.0 += 1;
+            }
+        }
+
+        // バッファ (MAX_HEIGHT) より上の余白まで押し上げられたブロックがあれば終わり
+        let is_pushed_out = ((start_pos + MAX_HEIGHT)..HEIGHT_WITH_PADDING)
+            .any(|y| columns.clone().any(|x| self.board[y][x] != 0));
+        if is_pushed_out {
+            return Some(TopOut::PushOut);
+        }
+        return None;
+    }
+
+    // せり上がりのブロックが残っている段の数
+    pub fn count_garbage_lines(&self) -> usize {
+        let start_pos = HEIGHT_PADDING / 2;
+        return (start_pos..(start_pos + MAX_HEIGHT))
+            .filter(|y| {
+                (start_pos..(start_pos + MAX_WIDTH)).any(|x| self.board[*y][x] == GARBAGE_CELL)
+            })
+            .count();
+    }
+
+    fn shift_lines(&mut self, from_y: usize) {
+        let start_pos = HEIGHT_PADDING / 2;
+
+        let mut y = from_y;
+        while y < start_pos + MAX_HEIGHT {
+            for x in start_pos..(start_pos + MAX_WIDTH) {
+                self.board[y][x] = self.board[y + 1][x];
+            }
+            y += 1;
+        }
+    }
+}
+
+impl StockTetrimono {
+    // 操作中のミノをホールドして、ホールド中のミノ (無ければ次のミノ) を出す
+    pub fn hold(&mut self, game_board: &mut GameBoard, mono_queue: &mut TetrimonoQueue) -> bool {
+        if self.is_used || game_board.tetrimono.is_none() {
+            return false;
+        }
+
+        let current = game_board.tetrimono.unwrap();
+        let next = match self.mono {
+            Some(mono) => mono.typ,
+            None => mono_queue.dequeue(),
+        };
+
+        // 向きは出現時に戻しておく
+        self.mono = Some(Tetromino::spawn(
+            current.typ,
+            game_board.rotation_system.get(),
+        ));
+        self.is_used = true;
+        game_board.init_tetrimono(next);
+        return true;
+    }
+
+    pub fn release(&mut self) {
+        self.is_used = false;
+    }
+}
+
+impl Default for TetrimonoQueue {
+    fn default() -> Self {
+        return TetrimonoQueue::new(0, RandomizerType::default());
+    }
+}
+
+impl TetrimonoQueue {
+    pub fn new(seed: u64, randomizer: RandomizerType) -> Self {
+        let mut queue = TetrimonoQueue {
+            queue: Vec::new(),
+            rng: Pcg32::new(seed),
+            randomizer: randomizer.create(),
+        };
+        queue.fill();
+        return queue;
+    }
+
+    pub fn is_enough_mono(&mut self) -> bool {
+        return self.queue.len() >= MIN_QUEUE_MONO_COUNT;
+    }
+
+    // ネクストに表示できるだけ先まで決めておく
+    fn fill(&mut self) {
+        while !self.is_enough_mono() {
+            let typ = self.randomizer.next(&mut self.rng);
+            self.queue.push(typ);
+        }
+    }
+
+    pub fn dequeue(&mut self) -> TetrominoType {
+        let typ = self.queue.remove(0);
+        self.fill();
+        return typ;
+    }
+}
+
+// テスト用 y 段目を I ミノ 1 本分 (5..9 列) だけ空けて埋め、I ミノを出す
+#[cfg(test)]
+impl GameBoard {
+    pub(crate) fn fill_row_for_i(&mut self, y: usize) {
+        for x in 2..12 {
+            if !(5..9).contains(&x) {
+                self.board[y][x] = 8;
+            }
+        }
+        self.init_tetrimono(TetrominoType::I);
+    }
+}
+
+#[test]
+fn i_mino_kicks_off_left_wall() {
+    let mut game_board = GameBoard::default();
+    game_board.init_tetrimono(TetrominoType::I);
+
+    assert!(game_board.try_rotate(RotateDirection::Right));
+    while game_board.simulate_move(MoveDirection::Left) {
+        game_board.apply_move(MoveDirection::Left);
+    }
+
+    assert!(game_board.try_rotate(RotateDirection::Right));
+    assert_eq!(game_board.tetrimono.unwrap().state(), 2);
+    assert_eq!(game_board.cursor.1, 2);
+}
+
+#[test]
+fn left_rotation_undoes_right_rotation() {
+    let mut game_board = GameBoard::default();
+    game_board.init_tetrimono(TetrominoType::L);
+    let spawned = game_board.tetrimono.unwrap().figure;
+
+    assert!(game_board.try_rotate(RotateDirection::Left));
+    assert_eq!(game_board.tetrimono.unwrap().state(), 3);
+    assert!(game_board.try_rotate(RotateDirection::Right));
+    assert_eq!(game_board.tetrimono.unwrap().figure, spawned);
+
+    assert!(game_board.try_rotate(RotateDirection::Half));
+    assert_eq!(game_board.tetrimono.unwrap().state(), 2);
+}
+
+#[test]
+fn hold_only_once_per_lock() {
+    let mut game_board = GameBoard::default();
+    let mut mono_queue = TetrimonoQueue::default();
+    let mut stock = StockTetrimono::default();
+    mono_queue.queue = TetrominoType::all();
+    game_board.init_tetrimono(TetrominoType::I);
+
+    assert!(stock.hold(&mut game_board, &mut mono_queue));
+    assert_eq!(stock.mono.unwrap().typ, TetrominoType::I);
+    assert_eq!(game_board.tetrimono.unwrap().typ, TetrominoType::T);
+    assert!(!stock.hold(&mut game_board, &mut mono_queue));
+
+    stock.release();
+    assert!(stock.hold(&mut game_board, &mut mono_queue));
+    assert_eq!(stock.mono.unwrap().typ, TetrominoType::T);
+    assert_eq!(game_board.tetrimono.unwrap().typ, TetrominoType::I);
+}
+
+#[test]
+fn t_spin_double_is_detected() {
+    let mut game_board = GameBoard::default();
+    // 下 2 段に T-spin ダブルの穴を作る (x = 4 が穴)
+    for x in 2..12 {
+        if x != 4 {
+            game_board.board[2][x] = 8;
+        }
+        if x != 3 && x != 4 && x != 5 {
+            game_board.board[3][x] = 8;
+        }
+    }
+    game_board.board[4][3] = 8;
+
+    game_board.init_tetrimono(TetrominoType::T);
+    game_board.cursor = (2, 3);
+    let mut tetrimono = game_board.tetrimono.unwrap();
+    tetrimono.rotation = 180;
+    tetrimono.figure = game_board.rotation_system.get().figure(TetrominoType::T, 2);
+    game_board.tetrimono = Some(tetrimono);
+    assert_eq!(game_board.detect_spin(), SpinType::None);
+
+    game_board.last_rotation = Some((RotateDirection::Right, 0));
+    assert_eq!(game_board.detect_spin(), SpinType::Full);
+    assert_eq!(game_board.clear_lines(), 0);
+    game_board.write_tetrimono();
+    assert_eq!(game_board.clear_lines(), 2);
+}
+
+#[test]
+fn ghost_lands_on_stack() {
+    let mut game_board = GameBoard::default();
+    game_board.board[5][5] = 8;
+    game_board.init_tetrimono(TetrominoType::O);
+
+    let ghost = game_board.get_ghost_cursor().unwrap();
+    assert_eq!(ghost, (6, game_board.cursor.1));
+}
+
+#[test]
+fn top_out_is_checked_after_clearing_lines() {
+    let mut game_board = GameBoard::default();
+    game_board.init_tetrimono(TetrominoType::T);
+
+    // 出現位置のまま置くと上の 1 マスだけ見えない段にはみ出す
+    assert_eq!(game_board.check_lock_out(false), None);
+    assert_eq!(
+        game_board.check_lock_out(true),
+        Some(TopOut::PartialLockOut)
+    );
+    game_board.cursor.0 += 1;
+    assert_eq!(game_board.check_lock_out(false), Some(TopOut::LockOut));
+
+    // 出現位置の段を I ミノで消す
+    let mut game_board = GameBoard::default();
+    game_board.fill_row_for_i(21);
+    game_board.write_tetrimono();
+
+    // 消す前に次のミノを出すと重なってしまう
+    game_board.init_tetrimono(TetrominoType::I);
+    assert_eq!(game_board.check_block_out(), Some(TopOut::BlockOut));
+    assert_eq!(game_board.clear_lines(), 1);
+    assert_eq!(game_board.check_block_out(), None);
+}
+
+#[test]
+fn same_seed_gives_same_queue() {
+    for randomizer in [RandomizerType::Bag14, RandomizerType::Tgm3] {
+        let mut a = TetrimonoQueue::new(1234, randomizer);
+        let mut b = TetrimonoQueue::new(1234, randomizer);
+        for _ in 0..50 {
+            assert_eq!(a.dequeue(), b.dequeue());
+        }
+    }
+
+    // 14 個の袋なら 14 個ごとに 2 個ずつ出る
+    let mut queue = TetrimonoQueue::new(1234, RandomizerType::Bag14);
+    let mut dealt = Vec::new();
+    for _ in 0..14 {
+        dealt.push(queue.dequeue());
+    }
+    for typ in TetrominoType::all() {
+        assert_eq!(dealt.iter().filter(|other| **other == typ).count(), 2);
+    }
+}
+
+#[test]
+fn garbage_pushes_the_stack_up() {
+    let mut game_board = GameBoard::default();
+    game_board.board[2][2] = 1;
+    game_board.init_tetrimono(TetrominoType::O);
+    let cursor = game_board.cursor;
+
+    assert_eq!(game_board.insert_garbage_line(3), None);
+    assert_eq!(game_board.board[3][2], 1);
+    assert_eq!(game_board.board[2][2], GARBAGE_CELL);
+    assert_eq!(game_board.board[2][5], 0);
+    assert_eq!(game_board.count_garbage_lines(), 1);
+    // 操作中のミノは重ならなければそのまま
+    assert_eq!(game_board.cursor, cursor);
+
+    // バッファより上まで押し上げたら終わり
+    let mut result = None;
+    for _ in 0..MAX_HEIGHT {
+        result = result.or(game_board.insert_garbage_line(0));
+    }
+    assert_eq!(result, Some(TopOut::PushOut));
+}
@@ -0,0 +1,187 @@
+use super::config::GameSettings;
+use super::event::GameEvent;
+use super::game::{Game, Inputs};
+use super::garbage::{GarbageHoles, GarbageRules};
+use super::random::random_seed;
+
+pub const PLAYER_COUNT: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersusResult {
+    // 勝ったプレイヤーの番号
+    Win(usize),
+    Draw,
+}
+
+// 2 人対戦 お互いの攻撃を相手のゲームに渡す
+#[derive(Clone)]
+pub struct Versus {
+    pub players: [Game; PLAYER_COUNT],
+    pub result: Option<VersusResult>,
+}
+
+impl Versus {
+    // 同じシードで始めて、2 人とも同じ順番でミノが出るようにする
+    pub fn new(settings: GameSettings, rules: &GarbageRules) -> Self {
+        let seed = settings.seed.unwrap_or_else(random_seed);
+        let settings = GameSettings {
+            seed: Some(seed),
+            ..settings
+        };
+
+        let mut players = [Game::new(settings), Game::new(settings)];
+        for game in players.iter_mut() {
+            game.garbage_holes = GarbageHoles::new(seed, rules.hole_change_percent);
+            game.garbage_rules = rules.clone();
+        }
+        return Versus {
+            players: players,
+            result: None,
+        };
+    }
+
+    pub fn is_over(&self) -> bool {
+        return self.result.is_some();
+    }
+
+    // 2 人分 1 フレーム進める 返すイベントはプレイヤーごと
+    pub fn step(&mut self, inputs: [Inputs; PLAYER_COUNT]) -> [Vec<GameEvent>; PLAYER_COUNT] {
+        let mut events = [Vec::new(), Vec::new()];
+        if self.is_over() {
+            return events;
+        }
+
+        for (i, game) in self.players.iter_mut().enumerate() {
+            events[i] = game.step(inputs[i]);
+        }
+
+        // 同じフレームの攻撃は両方とも届ける
+        for (i, player_events) in events.iter().enumerate() {
+            for event in player_events.iter() {
+                if let GameEvent::Attack(attack) = event {
+                    self.players[PLAYER_COUNT - 1 - i].receive_garbage(attack.lines);
+                }
+            }
+        }
+
+        self.result = self.judge();
+        return events;
+    }
+
+    // 目標を達成したら勝ち、積めなくなったら負け 同じフレームで並んだら引き分け
+    fn judge(&self) -> Option<VersusResult> {
+        if self.players.iter().all(|game| !game.is_over()) {
+            return None;
+        }
+
+        let rank = |game: &Game| match game.end {
+            Some(end) if end.is_cleared() => 2,
+            Some(_) => 0,
+            None => 1,
+        };
+        let ranks = [rank(&self.players[0]), rank(&self.players[1])];
+        if ranks[0] == ranks[1] {
+            return Some(VersusResult::Draw);
+        }
+        let winner = if ranks[0] > ranks[1] { 0 } else { 1 };
+        return Some(VersusResult::Win(winner));
+    }
+}
+
+#[test]
+fn attack_is_sent_and_raised_on_the_next_lock() {
+    let mut versus = Versus::new(
+        GameSettings {
+            seed: Some(1),
+            ..Default::default()
+        },
+        &GarbageRules::default(),
+    );
+
+    // I ミノで一番下の段を消すとパーフェクトクリアになる
+    versus.players[0].board.fill_row_for_i(2);
+
+    let hard_drop = Inputs {
+        hard_drop: true,
+        ..Default::default()
+    };
+    versus.step([hard_drop, Inputs::default()]);
+    assert_eq!(versus.players[1].pending_garbage_lines(), 10);
+
+    // ラインを消さずに置くとまとめてせり上がる
+    versus.step([Inputs::default(), hard_drop]);
+    assert_eq!(versus.players[1].pending_garbage_lines(), 0);
+    assert_eq!(versus.players[1].board.count_garbage_lines(), 10);
+    assert_eq!(versus.result, None);
+}
+
+#[test]
+fn garbage_waits_for_the_delay_and_rises_up_to_the_cap() {
+    let rules = GarbageRules {
+        delay_frames: 10,
+        cap_per_piece: Some(4),
+        ..Default::default()
+    };
+    let mut versus = Versus::new(
+        GameSettings {
+            seed: Some(1),
+            ..Default::default()
+        },
+        &rules,
+    );
+
+    versus.players[0].board.fill_row_for_i(2);
+
+    let hard_drop = Inputs {
+        hard_drop: true,
+        ..Default::default()
+    };
+    versus.step([hard_drop, Inputs::default()]);
+
+    // 待ち時間の間に置いてもせり上がらない
+    versus.step([Inputs::default(), hard_drop]);
+    assert_eq!(versus.players[1].board.count_garbage_lines(), 0);
+
+    for _ in 0..10 {
+        versus.step([Inputs::default(), Inputs::default()]);
+    }
+    versus.step([Inputs::default(), hard_drop]);
+    assert_eq!(versus.players[1].board.count_garbage_lines(), 4);
+    assert_eq!(versus.players[1].pending_garbage_lines(), 6);
+}
+
+#[test]
+fn cancel_before_receive_decides_whether_attacks_offset_garbage() {
+    for cancel_before_receive in [true, false] {
+        let rules = GarbageRules {
+            cancel_before_receive: cancel_before_receive,
+            ..Default::default()
+        };
+        let mut versus = Versus::new(
+            GameSettings {
+                seed: Some(1),
+                ..Default::default()
+            },
+            &rules,
+        );
+        versus.players[0].receive_garbage(4);
+        versus.players[0].board.fill_row_for_i(2);
+
+        let hard_drop = Inputs {
+            hard_drop: true,
+            ..Default::default()
+        };
+        versus.step([hard_drop, Inputs::default()]);
+
+        // パーフェクトクリアの 10 段を受けている 4 段と相殺するかどうか
+        let (pending, sent) = if cancel_before_receive {
+            (0, 6)
+        } else {
+            (4, 10)
+        };
+        assert_eq!(versus.players[0].pending_garbage_lines(), pending);
+        assert_eq!(versus.players[1].pending_garbage_lines(), sent);
+        // 消したミノではせり上がらない
+        assert_eq!(versus.players[0].board.count_garbage_lines(), 0);
+    }
+}